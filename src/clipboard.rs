@@ -0,0 +1,752 @@
+//! Clipboard access through `wl_data_device` and `zwp_primary_selection_device_v1`.
+//!
+//! The [`Clipboard`] resource reads the current selection asynchronously, reporting the data
+//! through [`ClipboardRead`], and sets the selection with data that is only produced once another
+//! client pastes it. The primary selection, pasted with the middle mouse button, is accessed the
+//! same way and can follow the text selected in the app through [`TextSelection`].
+
+use std::{fmt, io, sync::Arc};
+
+use bevy::prelude::*;
+use smithay_client_toolkit::{
+    data_device_manager::{
+        DataDeviceManagerState, ReadPipe, WritePipe,
+        data_device::{DataDevice, DataDeviceHandler},
+        data_offer::{DataOfferError, DataOfferHandler, DragOffer},
+        data_source::{CopyPasteSource, DataSourceHandler},
+    },
+    delegate_data_device, delegate_primary_selection,
+    primary_selection::{
+        PrimarySelectionManagerState,
+        device::{PrimarySelectionDevice, PrimarySelectionDeviceHandler},
+        selection::{PrimarySelectionSource, PrimarySelectionSourceHandler},
+    },
+    reexports::{
+        calloop::PostAction,
+        client::{
+            Connection, QueueHandle,
+            globals::GlobalList,
+            protocol::{
+                wl_data_device::WlDataDevice, wl_data_device_manager::DndAction,
+                wl_data_source::WlDataSource, wl_seat::WlSeat, wl_surface::WlSurface,
+            },
+        },
+        protocols::wp::primary_selection::zv1::client::{
+            zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1,
+            zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1,
+        },
+    },
+};
+
+use crate::state::SmithayRunnerState;
+
+/// The MIME types used for plain text, in order of preference.
+const TEXT_MIME_TYPES: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+];
+
+/// Produces the data of a selection for the requested MIME type.
+pub(crate) type SelectionData = Arc<dyn Fn(&str) -> Option<Vec<u8>> + Send + Sync>;
+
+/// The selection a clipboard operation applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionTarget {
+    /// The regular clipboard, set by copying and read by pasting.
+    Clipboard,
+    /// The primary selection, set by selecting text and read by middle-click pasting.
+    Primary,
+}
+
+/// Reads and sets the clipboard and the primary selection.
+///
+/// Setting a selection requires a recent input event, since compositors only accept selections
+/// set in response to user input. Selections set before any input event are dropped.
+#[derive(Resource, Default)]
+pub struct Clipboard {
+    mime_types: Vec<String>,
+    primary_mime_types: Vec<String>,
+    requests: Vec<ClipboardRequest>,
+    next_read: u64,
+}
+
+impl Clipboard {
+    /// Returns the MIME types offered by the current selection.
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    /// Reads the current selection as `mime_type`, reporting the data through [`ClipboardRead`].
+    pub fn read(&mut self, mime_type: impl Into<String>) -> ClipboardReadId {
+        self.read_from(SelectionTarget::Clipboard, mime_type.into())
+    }
+
+    /// Reads the current selection as text, if it is offered as text.
+    ///
+    /// The data of the resulting [`ClipboardRead`] is UTF-8 for all but the legacy `STRING` type.
+    pub fn read_text(&mut self) -> Option<ClipboardReadId> {
+        let mime_type = text_mime_type(&self.mime_types)?;
+        Some(self.read_from(SelectionTarget::Clipboard, mime_type))
+    }
+
+    /// Sets the selection, offering it as `mime_types`.
+    ///
+    /// `data` is called whenever a client pastes the selection, with the MIME type it requested.
+    /// Returning `None` sends no data.
+    pub fn set(
+        &mut self,
+        mime_types: Vec<String>,
+        data: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) {
+        self.requests.push(ClipboardRequest::Set(
+            SelectionTarget::Clipboard,
+            mime_types,
+            Arc::new(data),
+        ));
+    }
+
+    /// Sets the selection to `text`.
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.requests
+            .push(text_request(SelectionTarget::Clipboard, text.into()));
+    }
+
+    /// Clears the selection.
+    pub fn clear(&mut self) {
+        self.requests
+            .push(ClipboardRequest::Clear(SelectionTarget::Clipboard));
+    }
+
+    /// Returns the MIME types offered by the current primary selection.
+    pub fn primary_mime_types(&self) -> &[String] {
+        &self.primary_mime_types
+    }
+
+    /// Reads the current primary selection as `mime_type`, reporting the data through
+    /// [`ClipboardRead`].
+    pub fn read_primary(&mut self, mime_type: impl Into<String>) -> ClipboardReadId {
+        self.read_from(SelectionTarget::Primary, mime_type.into())
+    }
+
+    /// Reads the current primary selection as text, if it is offered as text.
+    pub fn read_primary_text(&mut self) -> Option<ClipboardReadId> {
+        let mime_type = text_mime_type(&self.primary_mime_types)?;
+        Some(self.read_from(SelectionTarget::Primary, mime_type))
+    }
+
+    /// Sets the primary selection, offering it as `mime_types`.
+    ///
+    /// `data` is called whenever a client pastes the primary selection, see [`Clipboard::set`].
+    pub fn set_primary(
+        &mut self,
+        mime_types: Vec<String>,
+        data: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) {
+        self.requests.push(ClipboardRequest::Set(
+            SelectionTarget::Primary,
+            mime_types,
+            Arc::new(data),
+        ));
+    }
+
+    /// Sets the primary selection to `text`.
+    pub fn set_primary_text(&mut self, text: impl Into<String>) {
+        self.requests
+            .push(text_request(SelectionTarget::Primary, text.into()));
+    }
+
+    /// Clears the primary selection.
+    pub fn clear_primary(&mut self) {
+        self.requests
+            .push(ClipboardRequest::Clear(SelectionTarget::Primary));
+    }
+
+    fn read_from(&mut self, target: SelectionTarget, mime_type: String) -> ClipboardReadId {
+        let id = ClipboardReadId(self.next_read);
+        self.next_read += 1;
+        self.requests
+            .push(ClipboardRequest::Read(target, id, mime_type));
+        id
+    }
+}
+
+/// Returns the preferred text MIME type among `offered`.
+fn text_mime_type(offered: &[String]) -> Option<String> {
+    TEXT_MIME_TYPES
+        .into_iter()
+        .find(|mime_type| offered.iter().any(|offered| offered == mime_type))
+        .map(String::from)
+}
+
+fn text_request(target: SelectionTarget, text: String) -> ClipboardRequest {
+    ClipboardRequest::Set(
+        target,
+        TEXT_MIME_TYPES.into_iter().map(String::from).collect(),
+        Arc::new(move |_| Some(text.clone().into_bytes())),
+    )
+}
+
+enum ClipboardRequest {
+    Read(SelectionTarget, ClipboardReadId, String),
+    Set(SelectionTarget, Vec<String>, SelectionData),
+    Clear(SelectionTarget),
+}
+
+/// The text selected in a text field of the app.
+///
+/// Adding this component opts into updating the primary selection: whenever it changes to a
+/// non-empty text, the text becomes the primary selection. Deselecting text keeps the primary
+/// selection, as other clients do.
+#[derive(Component, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TextSelection(pub String);
+
+/// Sets the primary selection to changed [`TextSelection`]s.
+pub(crate) fn sync_primary_selection(
+    selections: Query<&TextSelection, Changed<TextSelection>>,
+    mut clipboard: ResMut<Clipboard>,
+) {
+    if let Some(TextSelection(text)) = selections.iter().filter(|s| !s.0.is_empty()).last() {
+        clipboard.set_primary_text(text.clone());
+    }
+}
+
+/// Identifies a read started with [`Clipboard::read`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClipboardReadId(u64);
+
+/// An error that occurred while reading the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardError {
+    /// There is no selection to read.
+    Empty,
+    /// The selection is not offered as the requested MIME type.
+    UnsupportedMimeType,
+    /// Receiving the data failed.
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::Empty => write!(f, "the selection is empty"),
+            ClipboardError::UnsupportedMimeType => {
+                write!(f, "the selection is not offered as the requested MIME type")
+            }
+            ClipboardError::Io(kind) => write!(f, "failed to receive the selection: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+/// Sent when a read started with [`Clipboard::read`] or [`Clipboard::read_primary`] completes.
+#[derive(Event, Debug, Clone)]
+pub struct ClipboardRead {
+    pub id: ClipboardReadId,
+    pub target: SelectionTarget,
+    pub mime_type: String,
+    pub result: Result<Vec<u8>, ClipboardError>,
+}
+
+/// Sent when the selection changes, with the MIME types it is offered as.
+///
+/// The MIME types are empty if the selection was cleared.
+#[derive(Event, Debug, Clone)]
+pub struct ClipboardChanged {
+    pub target: SelectionTarget,
+    pub mime_types: Vec<String>,
+}
+
+pub(crate) struct ClipboardState {
+    manager: Option<DataDeviceManagerState>,
+    data_devices: Vec<DataDevice>,
+    source: Option<(CopyPasteSource, SelectionData)>,
+    primary_manager: Option<PrimarySelectionManagerState>,
+    primary_devices: Vec<PrimarySelectionDevice>,
+    primary_source: Option<(PrimarySelectionSource, SelectionData)>,
+}
+
+impl ClipboardState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            manager: DataDeviceManagerState::bind(globals, qh).ok(),
+            data_devices: Vec::new(),
+            source: None,
+            primary_manager: PrimarySelectionManagerState::bind(globals, qh).ok(),
+            primary_devices: Vec::new(),
+            primary_source: None,
+        }
+    }
+
+    /// Creates the data devices of a new seat.
+    pub(crate) fn add_seat(&mut self, seat: &WlSeat, qh: &QueueHandle<SmithayRunnerState>) {
+        if let Some(manager) = &self.manager {
+            self.data_devices.push(manager.get_data_device(qh, seat));
+        }
+        if let Some(primary_manager) = &self.primary_manager {
+            self.primary_devices
+                .push(primary_manager.get_selection_device(qh, seat));
+        }
+    }
+
+    /// Destroys the data devices of a removed seat.
+    pub(crate) fn remove_seat(&mut self, seat: &WlSeat) {
+        self.data_devices
+            .retain(|data_device| data_device.data().seat() != seat);
+        self.primary_devices
+            .retain(|primary_device| primary_device.data().seat() != seat);
+    }
+
+    pub(crate) fn manager(&self) -> Option<&DataDeviceManagerState> {
+        self.manager.as_ref()
+    }
+
+    /// Returns the data device of `seat`, or of any seat if `seat` is `None`.
+    pub(crate) fn data_device(&self, seat: Option<&WlSeat>) -> Option<&DataDevice> {
+        match seat {
+            Some(seat) => self
+                .data_devices
+                .iter()
+                .find(|data_device| data_device.data().seat() == seat),
+            None => self.data_devices.first(),
+        }
+    }
+
+    /// Returns the primary selection device of `seat`, or of any seat if `seat` is `None`.
+    fn primary_device(&self, seat: Option<&WlSeat>) -> Option<&PrimarySelectionDevice> {
+        match seat {
+            Some(seat) => self
+                .primary_devices
+                .iter()
+                .find(|primary_device| primary_device.data().seat() == seat),
+            None => self.primary_devices.first(),
+        }
+    }
+
+    /// Returns the data for pastes of the selection of `target`, if this client owns it.
+    fn selection_data(&self, target: SelectionTarget) -> Option<&SelectionData> {
+        match target {
+            SelectionTarget::Clipboard => self.source.as_ref().map(|(_, data)| data),
+            SelectionTarget::Primary => self.primary_source.as_ref().map(|(_, data)| data),
+        }
+    }
+}
+
+impl SmithayRunnerState {
+    /// Reads and sets the selections as requested through the [`Clipboard`] resource.
+    pub(crate) fn update_clipboard(&mut self, qh: &QueueHandle<SmithayRunnerState>) {
+        let requests = std::mem::take(&mut self.world_mut().resource_mut::<Clipboard>().requests);
+        for request in requests {
+            match request {
+                ClipboardRequest::Read(target, id, mime_type) => {
+                    self.read_selection(target, id, mime_type)
+                }
+                ClipboardRequest::Set(target, mime_types, data) => {
+                    let Some((seat, serial)) = self.latest_input_serial.clone() else {
+                        warn!("the selection can only be set after an input event");
+                        continue;
+                    };
+                    self.set_selection(target, &seat, serial, mime_types, data, qh);
+                }
+                ClipboardRequest::Clear(target) => {
+                    let Some((seat, serial)) = self.latest_input_serial.clone() else {
+                        warn!("the selection can only be cleared after an input event");
+                        continue;
+                    };
+                    self.clear_selection(target, &seat, serial);
+                }
+            }
+        }
+    }
+
+    fn set_selection(
+        &mut self,
+        target: SelectionTarget,
+        seat: &WlSeat,
+        serial: u32,
+        mime_types: Vec<String>,
+        data: SelectionData,
+        qh: &QueueHandle<SmithayRunnerState>,
+    ) {
+        let clipboard = &mut self.clipboard;
+        match target {
+            SelectionTarget::Clipboard => {
+                let (Some(manager), Some(data_device)) =
+                    (&clipboard.manager, clipboard.data_device(Some(seat)))
+                else {
+                    return;
+                };
+                let source = manager.create_copy_paste_source(qh, mime_types);
+                source.set_selection(data_device, serial);
+                clipboard.source = Some((source, data));
+            }
+            SelectionTarget::Primary => {
+                let (Some(primary_manager), Some(primary_device)) = (
+                    &clipboard.primary_manager,
+                    clipboard.primary_device(Some(seat)),
+                ) else {
+                    return;
+                };
+                let source = primary_manager.create_selection_source(qh, mime_types);
+                source.set_selection(primary_device, serial);
+                clipboard.primary_source = Some((source, data));
+            }
+        }
+    }
+
+    fn clear_selection(&mut self, target: SelectionTarget, seat: &WlSeat, serial: u32) {
+        let clipboard = &mut self.clipboard;
+        match target {
+            SelectionTarget::Clipboard => {
+                if let Some(data_device) = clipboard.data_device(Some(seat)) {
+                    data_device.unset_selection(serial);
+                    clipboard.source = None;
+                }
+            }
+            SelectionTarget::Primary => {
+                if let Some(primary_device) = clipboard.primary_device(Some(seat)) {
+                    primary_device.unset_selection(serial);
+                    clipboard.primary_source = None;
+                }
+            }
+        }
+    }
+
+    /// Starts receiving the selection of `target` as `mime_type`.
+    fn read_selection(&mut self, target: SelectionTarget, id: ClipboardReadId, mime_type: String) {
+        let seat = self.latest_input_serial.as_ref().map(|(seat, _)| seat);
+        let result = match target {
+            SelectionTarget::Clipboard => {
+                match self
+                    .clipboard
+                    .data_device(seat)
+                    .and_then(|data_device| data_device.data().selection_offer())
+                {
+                    None => Err(ClipboardError::Empty),
+                    Some(offer)
+                        if !offer.with_mime_types(|offered| offered.contains(&mime_type)) =>
+                    {
+                        Err(ClipboardError::UnsupportedMimeType)
+                    }
+                    Some(offer) => offer.receive(mime_type.clone()).map_err(|err| match err {
+                        DataOfferError::Io(err) => ClipboardError::Io(err.kind()),
+                        DataOfferError::InvalidReceive => ClipboardError::Empty,
+                    }),
+                }
+            }
+            SelectionTarget::Primary => {
+                match self
+                    .clipboard
+                    .primary_device(seat)
+                    .and_then(|primary_device| primary_device.data().selection_offer())
+                {
+                    None => Err(ClipboardError::Empty),
+                    Some(offer)
+                        if !offer.with_mime_types(|offered| offered.contains(&mime_type)) =>
+                    {
+                        Err(ClipboardError::UnsupportedMimeType)
+                    }
+                    Some(offer) => offer
+                        .receive(mime_type.clone())
+                        .map_err(|err| ClipboardError::Io(err.kind())),
+                }
+            }
+        };
+        let read_pipe = match result {
+            Ok(read_pipe) => read_pipe,
+            Err(err) => {
+                self.world_mut().send_event(ClipboardRead {
+                    id,
+                    target,
+                    mime_type,
+                    result: Err(err),
+                });
+                return;
+            }
+        };
+
+        self.receive_pipe(read_pipe, move |state, result| {
+            state.world_mut().send_event(ClipboardRead {
+                id,
+                target,
+                mime_type,
+                result: result.map_err(|err| ClipboardError::Io(err.kind())),
+            });
+        });
+    }
+
+    /// Reads `read_pipe` to its end without blocking the event loop, then calls `done` with the
+    /// data.
+    pub(crate) fn receive_pipe(
+        &mut self,
+        read_pipe: ReadPipe,
+        done: impl FnOnce(&mut SmithayRunnerState, io::Result<Vec<u8>>) + 'static,
+    ) {
+        let mut data = Vec::new();
+        let mut done = Some(done);
+        let inserted = self
+            .loop_handle
+            .insert_source(read_pipe, move |_, file, state| {
+                let mut buffer = [0; 4096];
+                let result = match io::Read::read(&mut &**file, &mut buffer) {
+                    Ok(0) => Ok(std::mem::take(&mut data)),
+                    Ok(len) => {
+                        data.extend_from_slice(&buffer[..len]);
+                        return PostAction::Continue;
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {
+                        return PostAction::Continue;
+                    }
+                    Err(err) => Err(err),
+                };
+                if let Some(done) = done.take() {
+                    done(state, result);
+                }
+                PostAction::Remove
+            });
+        if let Err(err) = inserted {
+            warn!("failed to receive data from another client: {}", err.error);
+        }
+    }
+
+    /// Writes the data of the selection of `target` for `mime_type` to `write_pipe` without
+    /// blocking the event loop.
+    fn send_selection(&mut self, target: SelectionTarget, mime_type: &str, write_pipe: WritePipe) {
+        let Some(data) = self
+            .clipboard
+            .selection_data(target)
+            .and_then(|data| data(mime_type))
+        else {
+            return;
+        };
+        self.send_pipe(write_pipe, data);
+    }
+
+    /// Writes `data` to `write_pipe` without blocking the event loop.
+    pub(crate) fn send_pipe(&mut self, write_pipe: WritePipe, data: Vec<u8>) {
+        let mut written = 0;
+        let inserted = self
+            .loop_handle
+            .insert_source(write_pipe, move |_, file, _| {
+                let end = data.len().min(written + 4096);
+                match io::Write::write(&mut &**file, &data[written..end]) {
+                    Ok(len) => {
+                        written += len;
+                        if written < data.len() {
+                            PostAction::Continue
+                        } else {
+                            PostAction::Remove
+                        }
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => PostAction::Continue,
+                    Err(_) => PostAction::Remove,
+                }
+            });
+        if let Err(err) = inserted {
+            warn!("failed to send data to another client: {}", err.error);
+        }
+    }
+}
+
+impl DataDeviceHandler for SmithayRunnerState {
+    fn enter(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        data_device: &WlDataDevice,
+        _: f64,
+        _: f64,
+        surface: &WlSurface,
+    ) {
+        self.drag_enter(data_device, surface);
+    }
+
+    fn leave(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &WlDataDevice) {
+        self.drag_leave();
+    }
+
+    fn motion(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &WlDataDevice, _: f64, _: f64) {}
+
+    fn selection(&mut self, _: &Connection, _: &QueueHandle<Self>, data_device: &WlDataDevice) {
+        let Some(data_device) = self
+            .clipboard
+            .data_devices
+            .iter()
+            .find(|device| device.inner() == data_device)
+        else {
+            return;
+        };
+        let mime_types = data_device
+            .data()
+            .selection_offer()
+            .map(|offer| offer.with_mime_types(<[String]>::to_vec))
+            .unwrap_or_default();
+
+        let world = self.world_mut();
+        world.resource_mut::<Clipboard>().mime_types = mime_types.clone();
+        world.send_event(ClipboardChanged {
+            target: SelectionTarget::Clipboard,
+            mime_types,
+        });
+    }
+
+    fn drop_performed(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &WlDataDevice) {
+        self.drag_drop();
+    }
+}
+
+impl DataOfferHandler for SmithayRunnerState {
+    fn source_actions(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &mut DragOffer,
+        _: DndAction,
+    ) {
+    }
+
+    fn selected_action(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        offer: &mut DragOffer,
+        action: DndAction,
+    ) {
+        self.drag_selected_action(offer, action);
+    }
+}
+
+impl DataSourceHandler for SmithayRunnerState {
+    fn accept_mime(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &WlDataSource,
+        _: Option<String>,
+    ) {
+    }
+
+    fn send_request(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &WlDataSource,
+        mime: String,
+        fd: WritePipe,
+    ) {
+        if self
+            .clipboard
+            .source
+            .as_ref()
+            .is_some_and(|(current, _)| current.inner() == source)
+        {
+            self.send_selection(SelectionTarget::Clipboard, &mime, fd);
+        } else {
+            self.send_drag_data(source, &mime, fd);
+        }
+    }
+
+    fn cancelled(&mut self, _: &Connection, _: &QueueHandle<Self>, source: &WlDataSource) {
+        // Another client replaced the selection.
+        if self
+            .clipboard
+            .source
+            .as_ref()
+            .is_some_and(|(current, _)| current.inner() == source)
+        {
+            self.clipboard.source = None;
+        } else {
+            self.drag_cancelled(source);
+        }
+    }
+
+    fn dnd_dropped(&mut self, _: &Connection, _: &QueueHandle<Self>, source: &WlDataSource) {
+        self.drag_dropped(source);
+    }
+
+    fn dnd_finished(&mut self, _: &Connection, _: &QueueHandle<Self>, source: &WlDataSource) {
+        self.drag_finished(source);
+    }
+
+    fn action(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &WlDataSource,
+        action: DndAction,
+    ) {
+        self.drag_action(source, action);
+    }
+}
+
+impl PrimarySelectionDeviceHandler for SmithayRunnerState {
+    fn selection(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        primary_selection_device: &ZwpPrimarySelectionDeviceV1,
+    ) {
+        let Some(primary_device) = self
+            .clipboard
+            .primary_devices
+            .iter()
+            .find(|device| device.inner() == primary_selection_device)
+        else {
+            return;
+        };
+        let mime_types = primary_device
+            .data()
+            .selection_offer()
+            .map(|offer| offer.with_mime_types(<[String]>::to_vec))
+            .unwrap_or_default();
+
+        let world = self.world_mut();
+        world.resource_mut::<Clipboard>().primary_mime_types = mime_types.clone();
+        world.send_event(ClipboardChanged {
+            target: SelectionTarget::Primary,
+            mime_types,
+        });
+    }
+}
+
+impl PrimarySelectionSourceHandler for SmithayRunnerState {
+    fn send_request(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+        mime: String,
+        write_pipe: WritePipe,
+    ) {
+        if self
+            .clipboard
+            .primary_source
+            .as_ref()
+            .is_some_and(|(current, _)| current.inner() == source)
+        {
+            self.send_selection(SelectionTarget::Primary, &mime, write_pipe);
+        }
+    }
+
+    fn cancelled(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        source: &ZwpPrimarySelectionSourceV1,
+    ) {
+        // Another client replaced the primary selection.
+        if self
+            .clipboard
+            .primary_source
+            .as_ref()
+            .is_some_and(|(current, _)| current.inner() == source)
+        {
+            self.clipboard.primary_source = None;
+        }
+    }
+}
+
+delegate_data_device!(SmithayRunnerState);
+delegate_primary_selection!(SmithayRunnerState);
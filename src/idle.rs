@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use bevy::{ecs::entity::EntityHashMap, prelude::*};
+use smithay_client_toolkit::{
+    globals::GlobalData,
+    reexports::{
+        client::{
+            Connection, Dispatch, QueueHandle, globals::GlobalList, protocol::wl_seat::WlSeat,
+        },
+        protocols::{
+            ext::idle_notify::v1::client::{
+                ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+                ext_idle_notifier_v1::ExtIdleNotifierV1,
+            },
+            wp::idle_inhibit::zv1::client::{
+                zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1,
+                zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+            },
+        },
+    },
+};
+
+use crate::{smithay_windows::SmithayWindows, state::SmithayRunnerState};
+
+/// Keeps the screen awake while the window it is attached to is visible.
+///
+/// An idle inhibitor is created on the window's surface while this component is present and
+/// destroyed when it is removed or the window is despawned. The compositor only honours the
+/// inhibitor while the surface is visible.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct IdleInhibit;
+
+pub(crate) struct IdleInhibitState {
+    manager: Option<ZwpIdleInhibitManagerV1>,
+    inhibitors: EntityHashMap<ZwpIdleInhibitorV1>,
+}
+
+impl IdleInhibitState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            manager: globals.bind(qh, 1..=1, GlobalData).ok(),
+            inhibitors: default(),
+        }
+    }
+}
+
+/// The user idle timeouts to be notified about through [`UserIdle`] and [`UserResumed`].
+///
+/// Each timeout is watched on every seat known to the compositor. Timeouts can be changed at any
+/// time; notifications for removed timeouts are dropped.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct IdleTimeouts {
+    pub timeouts: Vec<Duration>,
+}
+
+/// Sent when the user has been idle on `seat` for at least `timeout`.
+///
+/// Every seat is watched separately, so this is sent once per seat. `seat` refers to the entity
+/// carrying its [`Seat`](crate::prelude::Seat).
+#[derive(Event, Debug, Clone)]
+pub struct UserIdle {
+    pub seat: Entity,
+    pub timeout: Duration,
+}
+
+/// Sent when the user becomes active again on `seat` after a [`UserIdle`] event for `timeout`.
+#[derive(Event, Debug, Clone)]
+pub struct UserResumed {
+    pub seat: Entity,
+    pub timeout: Duration,
+}
+
+pub(crate) struct IdleNotificationData {
+    seat: WlSeat,
+    timeout: Duration,
+}
+
+pub(crate) struct IdleNotifyState {
+    notifier: Option<ExtIdleNotifierV1>,
+    notifications: Vec<(WlSeat, Duration, ExtIdleNotificationV1)>,
+}
+
+impl IdleNotifyState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            notifier: globals.bind(qh, 1..=1, GlobalData).ok(),
+            notifications: Vec::new(),
+        }
+    }
+}
+
+impl SmithayRunnerState {
+    /// Creates and destroys idle notifications to match [`IdleTimeouts`] and the current seats.
+    pub(crate) fn update_idle_notifications(&mut self, qh: &QueueHandle<Self>) {
+        let Some(notifier) = self.idle_notify.notifier.clone() else {
+            return;
+        };
+
+        let timeouts = &self.app.world().resource::<IdleTimeouts>().timeouts;
+        let seats = self.seat_state.seats().collect::<Vec<_>>();
+
+        self.idle_notify
+            .notifications
+            .retain(|(seat, timeout, notification)| {
+                let keep = seats.contains(seat) && timeouts.contains(timeout);
+                if !keep {
+                    notification.destroy();
+                }
+                keep
+            });
+
+        for seat in &seats {
+            for timeout in timeouts {
+                if self
+                    .idle_notify
+                    .notifications
+                    .iter()
+                    .any(|(s, t, _)| s == seat && t == timeout)
+                {
+                    continue;
+                }
+                let notification = notifier.get_idle_notification(
+                    timeout.as_millis().try_into().unwrap_or(u32::MAX),
+                    seat,
+                    qh,
+                    IdleNotificationData {
+                        seat: seat.clone(),
+                        timeout: *timeout,
+                    },
+                );
+                self.idle_notify
+                    .notifications
+                    .push((seat.clone(), *timeout, notification));
+            }
+        }
+    }
+
+    /// Creates and destroys idle inhibitors to match the windows carrying [`IdleInhibit`].
+    pub(crate) fn update_idle_inhibitors(&mut self, qh: &QueueHandle<Self>) {
+        let Some(manager) = self.idle_inhibit.manager.clone() else {
+            return;
+        };
+
+        let world = self.app.world_mut();
+        let mut query = world.query_filtered::<Entity, (With<IdleInhibit>, With<Window>)>();
+        let inhibited = query.iter(world).collect::<Vec<_>>();
+
+        self.idle_inhibit.inhibitors.retain(|entity, inhibitor| {
+            let keep = inhibited.contains(entity);
+            if !keep {
+                inhibitor.destroy();
+            }
+            keep
+        });
+
+        let smithay_windows = world.non_send_resource::<SmithayWindows>();
+        for entity in inhibited {
+            if self.idle_inhibit.inhibitors.contains_key(&entity) {
+                continue;
+            }
+            // The surface is created at the start of the next frame for new windows.
+            let Some(surface) = smithay_windows.wl_surface(entity) else {
+                continue;
+            };
+            let inhibitor = manager.create_inhibitor(surface, qh, ());
+            self.idle_inhibit.inhibitors.insert(entity, inhibitor);
+        }
+    }
+}
+
+impl Dispatch<ZwpIdleInhibitManagerV1, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpIdleInhibitManagerV1,
+        _: <ZwpIdleInhibitManagerV1 as smithay_client_toolkit::reexports::client::Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_idle_inhibit_manager_v1 has no events")
+    }
+}
+
+impl Dispatch<ZwpIdleInhibitorV1, ()> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpIdleInhibitorV1,
+        _: <ZwpIdleInhibitorV1 as smithay_client_toolkit::reexports::client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_idle_inhibitor_v1 has no events")
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ExtIdleNotifierV1,
+        _: <ExtIdleNotifierV1 as smithay_client_toolkit::reexports::client::Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("ext_idle_notifier_v1 has no events")
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, IdleNotificationData> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        data: &IdleNotificationData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(seat) = state.seat_devices(&data.seat).map(|devices| devices.entity) else {
+            return;
+        };
+        match event {
+            ext_idle_notification_v1::Event::Idled => {
+                state.world_mut().send_event(UserIdle {
+                    seat,
+                    timeout: data.timeout,
+                });
+            }
+            ext_idle_notification_v1::Event::Resumed => {
+                state.world_mut().send_event(UserResumed {
+                    seat,
+                    timeout: data.timeout,
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    asset::{Assets, Handle},
+    color::ColorToComponents,
+    image::{Image, TextureAtlas, TextureAtlasLayout},
+    log::warn,
+    math::{URect, UVec2},
+    prelude::Component,
+    window::{SystemCursorIcon, Window},
+};
+use smithay_client_toolkit::{
+    reexports::client::{Connection, Proxy, protocol::wl_shm},
+    seat::pointer::{CursorIcon as Shape, PointerData, ThemedPointer},
+    shm::slot::{Buffer, SlotPool},
+};
+
+use crate::state::SmithayRunnerState;
+
+/// Insert into a window entity to set the cursor shown while a pointer is over it.
+///
+/// This replaces the `CursorIcon` component of `bevy_winit`, which is not used by this crate and
+/// has no effect on windows created by it.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub enum SmithayCursorIcon {
+    /// Custom cursor image.
+    Custom(CustomCursor),
+    /// System provided cursor icon.
+    System(SystemCursorIcon),
+}
+
+impl Default for SmithayCursorIcon {
+    fn default() -> Self {
+        SmithayCursorIcon::System(Default::default())
+    }
+}
+
+impl From<SystemCursorIcon> for SmithayCursorIcon {
+    fn from(icon: SystemCursorIcon) -> Self {
+        SmithayCursorIcon::System(icon)
+    }
+}
+
+impl From<CustomCursor> for SmithayCursorIcon {
+    fn from(cursor: CustomCursor) -> Self {
+        SmithayCursorIcon::Custom(cursor)
+    }
+}
+
+/// Custom cursor image data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CustomCursor {
+    /// Use an image as the cursor.
+    Image(CustomCursorImage),
+}
+
+/// A custom cursor created from an image.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CustomCursorImage {
+    /// Handle to the image to use as the cursor.
+    pub handle: Handle<Image>,
+    /// An optional texture atlas used to render the image.
+    pub texture_atlas: Option<TextureAtlas>,
+    /// Whether the image should be flipped along its x-axis, along with the hotspot.
+    pub flip_x: bool,
+    /// Whether the image should be flipped along its y-axis, along with the hotspot.
+    pub flip_y: bool,
+    /// An optional region of the image to render, instead of the full image.
+    ///
+    /// When used with a texture atlas, the rect is offset by the top-left corner of the atlas
+    /// region.
+    pub rect: Option<URect>,
+    /// X and Y coordinates of the hotspot in pixels, within the image bounds.
+    pub hotspot: (u16, u16),
+}
+
+/// Animates the cursor of a window by cycling through custom cursor images.
+///
+/// While present on a window entity, this takes precedence over the window's [`SmithayCursorIcon`].
+#[derive(Component, Debug, Clone)]
+pub struct AnimatedCursor {
+    /// The images shown in order, looping back to the first one after the last.
+    pub frames: Vec<CustomCursorImage>,
+    /// How long each frame is shown.
+    pub frame_duration: Duration,
+}
+
+/// The cursor last applied to the pointer, used to skip redundant updates.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum AppliedCursor {
+    Hidden,
+    System(SystemCursorIcon),
+    Image {
+        image: CustomCursorImage,
+        scale: i32,
+    },
+}
+
+/// A custom cursor image rendered into a shm buffer for a single buffer scale.
+struct CursorBuffer {
+    buffer: Buffer,
+    width: i32,
+    height: i32,
+    hotspot: (i32, i32),
+}
+
+/// Keeps the shm buffers of custom cursors in use, one per image and buffer scale.
+#[derive(Default)]
+pub(crate) struct CustomCursorCache {
+    pool: Option<SlotPool>,
+    buffers: HashMap<(CustomCursorImage, i32), CursorBuffer>,
+    animation_start: Option<Instant>,
+}
+
+/// Converts a Bevy SystemCursorIcon to a cursor shape.
+fn convert_system_cursor_icon(icon: SystemCursorIcon) -> Shape {
+    match icon {
+        SystemCursorIcon::Default => Shape::Default,
+        SystemCursorIcon::ContextMenu => Shape::ContextMenu,
+        SystemCursorIcon::Help => Shape::Help,
+        SystemCursorIcon::Pointer => Shape::Pointer,
+        SystemCursorIcon::Progress => Shape::Progress,
+        SystemCursorIcon::Wait => Shape::Wait,
+        SystemCursorIcon::Cell => Shape::Cell,
+        SystemCursorIcon::Crosshair => Shape::Crosshair,
+        SystemCursorIcon::Text => Shape::Text,
+        SystemCursorIcon::VerticalText => Shape::VerticalText,
+        SystemCursorIcon::Alias => Shape::Alias,
+        SystemCursorIcon::Copy => Shape::Copy,
+        SystemCursorIcon::Move => Shape::Move,
+        SystemCursorIcon::NoDrop => Shape::NoDrop,
+        SystemCursorIcon::NotAllowed => Shape::NotAllowed,
+        SystemCursorIcon::Grab => Shape::Grab,
+        SystemCursorIcon::Grabbing => Shape::Grabbing,
+        SystemCursorIcon::EResize => Shape::EResize,
+        SystemCursorIcon::NResize => Shape::NResize,
+        SystemCursorIcon::NeResize => Shape::NeResize,
+        SystemCursorIcon::NwResize => Shape::NwResize,
+        SystemCursorIcon::SResize => Shape::SResize,
+        SystemCursorIcon::SeResize => Shape::SeResize,
+        SystemCursorIcon::SwResize => Shape::SwResize,
+        SystemCursorIcon::WResize => Shape::WResize,
+        SystemCursorIcon::EwResize => Shape::EwResize,
+        SystemCursorIcon::NsResize => Shape::NsResize,
+        SystemCursorIcon::NeswResize => Shape::NeswResize,
+        SystemCursorIcon::NwseResize => Shape::NwseResize,
+        SystemCursorIcon::ColResize => Shape::ColResize,
+        SystemCursorIcon::RowResize => Shape::RowResize,
+        SystemCursorIcon::AllScroll => Shape::AllScroll,
+        SystemCursorIcon::ZoomIn => Shape::ZoomIn,
+        SystemCursorIcon::ZoomOut => Shape::ZoomOut,
+    }
+}
+
+/// Renders the region of a custom cursor image into premultiplied ARGB8888 pixels, upscaled by
+/// `scale`.
+///
+/// Returns `None` if the image or its texture atlas layout is not loaded yet.
+pub(crate) fn render_custom_cursor(
+    cursor: &CustomCursorImage,
+    scale: i32,
+    images: &Assets<Image>,
+    texture_atlases: Option<&Assets<TextureAtlasLayout>>,
+) -> Option<(Vec<u8>, UVec2, (i32, i32))> {
+    let image = images.get(&cursor.handle)?;
+    let atlas_rect = match &cursor.texture_atlas {
+        Some(texture_atlas) => Some(texture_atlas.texture_rect(texture_atlases?)?),
+        None => None,
+    };
+    let rect = match (atlas_rect, cursor.rect) {
+        (None, None) => URect::from_corners(UVec2::ZERO, image.size()),
+        (None, Some(rect)) => rect,
+        (Some(atlas_rect), None) => atlas_rect,
+        (Some(atlas_rect), Some(rect)) => URect::from_corners(
+            atlas_rect.min + rect.min,
+            (atlas_rect.min + rect.max).min(atlas_rect.max),
+        ),
+    };
+
+    let size = rect.size();
+    let scale = scale as u32;
+    let mut pixels = Vec::with_capacity((size.x * size.y * scale * scale * 4) as usize);
+    for y in 0..size.y * scale {
+        for x in 0..size.x * scale {
+            let mut source_x = x / scale;
+            let mut source_y = y / scale;
+            if cursor.flip_x {
+                source_x = size.x - 1 - source_x;
+            }
+            if cursor.flip_y {
+                source_y = size.y - 1 - source_y;
+            }
+            let [r, g, b, a] = image
+                .get_color_at(rect.min.x + source_x, rect.min.y + source_y)
+                .map(|color| color.to_srgba().to_f32_array())
+                .unwrap_or_default();
+            // wl_shm expects premultiplied alpha in little endian byte order.
+            pixels.extend_from_slice(&[
+                (b * a * 255.0) as u8,
+                (g * a * 255.0) as u8,
+                (r * a * 255.0) as u8,
+                (a * 255.0) as u8,
+            ]);
+        }
+    }
+
+    let (mut hotspot_x, mut hotspot_y) = (cursor.hotspot.0 as u32, cursor.hotspot.1 as u32);
+    if cursor.flip_x {
+        hotspot_x = size.x.saturating_sub(1 + hotspot_x);
+    }
+    if cursor.flip_y {
+        hotspot_y = size.y.saturating_sub(1 + hotspot_y);
+    }
+
+    Some((pixels, size * scale, (hotspot_x as i32, hotspot_y as i32)))
+}
+
+impl SmithayRunnerState {
+    /// Applies the [`SmithayCursorIcon`] and cursor visibility of the windows under the seats'
+    /// pointers.
+    ///
+    /// The cursor shape device is used when the compositor supports `wp_cursor_shape_v1`,
+    /// otherwise the cursor is loaded from the XCURSOR theme. Custom cursor images are uploaded
+    /// to a wl_shm buffer matching the window's scale factor.
+    pub(crate) fn update_cursor(&mut self, conn: &Connection) {
+        let mut animating = false;
+        for index in 0..self.seats.len() {
+            animating |= self.update_seat_cursor(index, conn);
+        }
+        if !animating {
+            self.custom_cursors.animation_start = None;
+        }
+        self.evict_custom_cursors();
+    }
+
+    /// Drops the buffers of custom cursors that no seat shows and no window uses anymore.
+    fn evict_custom_cursors(&mut self) {
+        if self.custom_cursors.buffers.is_empty() {
+            return;
+        }
+        let world = self.app.world_mut();
+        let mut query =
+            world.query::<(&Window, Option<&SmithayCursorIcon>, Option<&AnimatedCursor>)>();
+        let mut in_use = HashSet::new();
+        for (window, icon, animation) in query.iter(world) {
+            let scale = (window.scale_factor().ceil() as i32).max(1);
+            if let Some(SmithayCursorIcon::Custom(CustomCursor::Image(image))) = icon {
+                in_use.insert((image, scale));
+            }
+            for frame in animation.iter().flat_map(|animation| &animation.frames) {
+                in_use.insert((frame, scale));
+            }
+        }
+        for devices in &self.seats {
+            if let Some(AppliedCursor::Image { image, scale }) = &devices.applied_cursor {
+                in_use.insert((image, *scale));
+            }
+        }
+        self.custom_cursors
+            .buffers
+            .retain(|(image, scale), _| in_use.contains(&(image, *scale)));
+    }
+
+    /// Applies the cursor of the window under the pointer of the seat at `index`.
+    ///
+    /// Returns `true` if the window shows an [`AnimatedCursor`].
+    fn update_seat_cursor(&mut self, index: usize, conn: &Connection) -> bool {
+        let devices = &self.seats[index];
+        let Some(pointer) = &devices.pointer else {
+            return false;
+        };
+        let Some(entity) = devices.pointer_focus else {
+            return false;
+        };
+        let world = self.app.world();
+        let Some(window) = world.get::<Window>(entity) else {
+            return false;
+        };
+        let scale = (window.scale_factor().ceil() as i32).max(1);
+
+        let mut animating = false;
+        let cursor = if !window.cursor_options.visible {
+            AppliedCursor::Hidden
+        } else if let Some(animation) = world
+            .get::<AnimatedCursor>(entity)
+            .filter(|animation| !animation.frames.is_empty())
+        {
+            animating = true;
+            let animation_start = *self
+                .custom_cursors
+                .animation_start
+                .get_or_insert_with(Instant::now);
+            let frame = if animation.frame_duration.is_zero() {
+                0
+            } else {
+                (animation_start.elapsed().as_nanos() / animation.frame_duration.as_nanos())
+                    as usize
+                    % animation.frames.len()
+            };
+            AppliedCursor::Image {
+                image: animation.frames[frame].clone(),
+                scale,
+            }
+        } else {
+            match world
+                .get::<SmithayCursorIcon>(entity)
+                .cloned()
+                .unwrap_or_default()
+            {
+                SmithayCursorIcon::System(icon) => AppliedCursor::System(icon),
+                SmithayCursorIcon::Custom(CustomCursor::Image(image)) => {
+                    AppliedCursor::Image { image, scale }
+                }
+            }
+        };
+        if devices.applied_cursor.as_ref() == Some(&cursor) {
+            return animating;
+        }
+
+        let result = match &cursor {
+            AppliedCursor::Hidden => pointer.hide_cursor(),
+            AppliedCursor::System(icon) => {
+                pointer.set_cursor(conn, convert_system_cursor_icon(*icon))
+            }
+            AppliedCursor::Image { image, scale } => {
+                if !self.set_custom_cursor(index, image, *scale) {
+                    // Try again once the image is loaded.
+                    return animating;
+                }
+                Ok(())
+            }
+        };
+        if let Err(err) = result {
+            warn!("failed to set cursor for window {}: {}", entity, err);
+        }
+        self.seats[index].applied_cursor = Some(cursor);
+        animating
+    }
+
+    /// Attaches a custom cursor image to the cursor surface of the seat at `index`.
+    ///
+    /// Returns `false` if the image is not loaded yet.
+    fn set_custom_cursor(&mut self, index: usize, image: &CustomCursorImage, scale: i32) -> bool {
+        let key = (image.clone(), scale);
+
+        if !self.custom_cursors.buffers.contains_key(&key) {
+            let world = self.app.world();
+            let Some(images) = world.get_resource::<Assets<Image>>() else {
+                warn!("custom cursors require the image assets to be available");
+                return true;
+            };
+            let texture_atlases = world.get_resource::<Assets<TextureAtlasLayout>>();
+            let Some((pixels, size, hotspot)) =
+                render_custom_cursor(image, scale, images, texture_atlases)
+            else {
+                return false;
+            };
+
+            let (width, height) = (size.x as i32, size.y as i32);
+            let pool = match &mut self.custom_cursors.pool {
+                Some(pool) => pool,
+                pool => match SlotPool::new(pixels.len(), &self.shm) {
+                    Ok(new_pool) => pool.insert(new_pool),
+                    Err(err) => {
+                        warn!("failed to create a shm pool for custom cursors: {}", err);
+                        return true;
+                    }
+                },
+            };
+            let (buffer, canvas) =
+                match pool.create_buffer(width, height, width * 4, wl_shm::Format::Argb8888) {
+                    Ok(buffer) => buffer,
+                    Err(err) => {
+                        warn!("failed to create a custom cursor buffer: {}", err);
+                        return true;
+                    }
+                };
+            canvas[..pixels.len()].copy_from_slice(&pixels);
+
+            self.custom_cursors.buffers.insert(
+                key.clone(),
+                CursorBuffer {
+                    buffer,
+                    width,
+                    height,
+                    hotspot,
+                },
+            );
+        }
+
+        let Some(pointer) = &self.seats[index].pointer else {
+            return true;
+        };
+        let cursor_buffer = &self.custom_cursors.buffers[&key];
+        attach_cursor_buffer(pointer, cursor_buffer, scale);
+        true
+    }
+}
+
+/// Shows `cursor_buffer` on the pointer's cursor surface.
+fn attach_cursor_buffer(pointer: &ThemedPointer, cursor_buffer: &CursorBuffer, scale: i32) {
+    let Some(serial) = pointer
+        .pointer()
+        .data::<PointerData>()
+        .and_then(|data| data.latest_enter_serial())
+    else {
+        return;
+    };
+
+    let surface = pointer.surface();
+    surface.set_buffer_scale(scale);
+    surface.attach(Some(cursor_buffer.buffer.wl_buffer()), 0, 0);
+    surface.damage_buffer(0, 0, cursor_buffer.width, cursor_buffer.height);
+    surface.commit();
+
+    pointer.pointer().set_cursor(
+        serial,
+        Some(surface),
+        cursor_buffer.hotspot.0,
+        cursor_buffer.hotspot.1,
+    );
+}
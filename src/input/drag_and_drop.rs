@@ -0,0 +1,573 @@
+use std::{ffi::OsString, os::unix::ffi::OsStringExt, path::PathBuf, sync::Arc};
+
+use bevy::{
+    asset::{Assets, Handle},
+    image::Image,
+    log::warn,
+    prelude::{Entity, Event, Resource},
+    window::{FileDragAndDrop, Window, WindowEvent},
+};
+use smithay_client_toolkit::{
+    data_device_manager::{
+        WritePipe, data_device::DataDeviceData, data_offer::DragOffer, data_source::DragSource,
+    },
+    reexports::client::{
+        Proxy, QueueHandle,
+        protocol::{
+            wl_data_device::WlDataDevice, wl_data_device_manager::DndAction,
+            wl_data_offer::WlDataOffer, wl_data_source::WlDataSource, wl_shm,
+            wl_surface::WlSurface,
+        },
+    },
+    shm::slot::{Buffer, SlotPool},
+};
+
+use crate::{
+    clipboard::SelectionData,
+    input::cursor::{CustomCursorImage, render_custom_cursor},
+    smithay_windows::SmithayWindows,
+    state::SmithayRunnerState,
+};
+
+const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+/// What a drop does with the dragged data, negotiated between the drag source and the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DragAction {
+    /// The target copies the data.
+    Copy,
+    /// The target moves the data, so the source deletes it once the drag finishes.
+    Move,
+    /// The target asks the user which action to take after the drop.
+    Ask,
+}
+
+impl DragAction {
+    fn to_dnd_action(self) -> DndAction {
+        match self {
+            DragAction::Copy => DndAction::Copy,
+            DragAction::Move => DndAction::Move,
+            DragAction::Ask => DndAction::Ask,
+        }
+    }
+
+    /// Converts the single action selected by the compositor, if any.
+    fn from_dnd_action(action: DndAction) -> Option<Self> {
+        if action.contains(DndAction::Copy) {
+            Some(DragAction::Copy)
+        } else if action.contains(DndAction::Move) {
+            Some(DragAction::Move)
+        } else if action.contains(DndAction::Ask) {
+            Some(DragAction::Ask)
+        } else {
+            None
+        }
+    }
+}
+
+/// The image shown under the pointer while dragging.
+#[derive(Debug, Clone)]
+pub struct DragIcon {
+    pub image: Handle<Image>,
+    /// The pixel of the image placed at the pointer.
+    pub hotspot: (u16, u16),
+}
+
+/// Data dragged out of a window, started with [`DragAndDrop::start_drag`].
+pub struct StartDrag {
+    /// The window the drag starts from.
+    pub window: Entity,
+    pub mime_types: Vec<String>,
+    /// The actions offered to drop targets.
+    pub actions: Vec<DragAction>,
+    pub icon: Option<DragIcon>,
+    data: SelectionData,
+}
+
+impl StartDrag {
+    /// Creates a drag from `window` offering the data as `mime_types` for copying.
+    ///
+    /// `data` is called whenever the drop target requests the data, with the requested MIME type.
+    /// Returning `None` sends no data.
+    pub fn new(
+        window: Entity,
+        mime_types: Vec<String>,
+        data: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            window,
+            mime_types,
+            actions: vec![DragAction::Copy],
+            icon: None,
+            data: Arc::new(data),
+        }
+    }
+
+    pub fn with_actions(mut self, actions: Vec<DragAction>) -> Self {
+        self.actions = actions;
+        self
+    }
+
+    pub fn with_icon(mut self, icon: DragIcon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+}
+
+/// Identifies a drag started with [`DragAndDrop::start_drag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DragId(u64);
+
+/// Starts dragging data out of windows to other clients.
+///
+/// A drag has to start while a mouse button or touch point is held down, usually in response to
+/// a [`MouseButtonInput`](bevy::input::mouse::MouseButtonInput) press. Only one drag can be active
+/// at a time; starting another one cancels it.
+#[derive(Resource, Default)]
+pub struct DragAndDrop {
+    requests: Vec<(DragId, StartDrag)>,
+    next_id: u64,
+}
+
+impl DragAndDrop {
+    /// Starts a drag, reporting its outcome through [`DragFinished`] or [`DragCancelled`].
+    pub fn start_drag(&mut self, drag: StartDrag) -> DragId {
+        let id = DragId(self.next_id);
+        self.next_id += 1;
+        self.requests.push((id, drag));
+        id
+    }
+}
+
+/// Sent when the action a drop would perform changes, e.g. as the drag moves between targets.
+#[derive(Event, Debug, Clone)]
+pub struct DragActionChanged {
+    pub id: DragId,
+    /// `None` if the target under the pointer accepts none of the offered actions.
+    pub action: Option<DragAction>,
+}
+
+/// Sent when the dragged data is dropped on a target that accepts it.
+///
+/// The target may still request the data until the drag is finished.
+#[derive(Event, Debug, Clone)]
+pub struct DragDropped {
+    pub id: DragId,
+}
+
+/// Sent when the drop target is done with the dragged data.
+///
+/// After a [`DragAction::Move`], the dragged data should be deleted.
+#[derive(Event, Debug, Clone)]
+pub struct DragFinished {
+    pub id: DragId,
+    pub action: Option<DragAction>,
+}
+
+/// Sent when a drag ends without being dropped on an accepting target.
+#[derive(Event, Debug, Clone)]
+pub struct DragCancelled {
+    pub id: DragId,
+}
+
+/// A drag started by this client.
+pub(crate) struct ActiveDrag {
+    id: DragId,
+    source: DragSource,
+    data: SelectionData,
+    action: Option<DragAction>,
+    /// The icon shown until the drag ends.
+    _icon: Option<DragIconSurface>,
+}
+
+struct DragIconSurface {
+    surface: WlSurface,
+    _pool: SlotPool,
+    _buffer: Buffer,
+}
+
+impl Drop for DragIconSurface {
+    fn drop(&mut self) {
+        self.surface.destroy();
+    }
+}
+
+/// Files dragged over or dropped on a window.
+pub(crate) struct FileDrag {
+    window: Entity,
+    offer: DragOffer,
+    /// The dragged files, once received from the source.
+    paths: Option<Vec<PathBuf>>,
+    /// The action selected by the compositor, empty while the drop would be rejected.
+    action: DndAction,
+    dropped: bool,
+}
+
+/// Decodes the local paths of a `text/uri-list`, skipping comments and non-file URIs.
+fn parse_uri_list(data: &[u8]) -> Vec<PathBuf> {
+    data.split(|&byte| byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !line.is_empty() && !line.starts_with(b"#"))
+        .filter_map(|uri| uri.strip_prefix(b"file://"))
+        .filter_map(|uri| {
+            // The host is empty or `localhost` for local files.
+            let path = &uri[uri.iter().position(|&byte| byte == b'/')?..];
+            Some(PathBuf::from(OsString::from_vec(percent_decode(path))))
+        })
+        .collect()
+}
+
+fn percent_decode(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut index = 0;
+    while index < input.len() {
+        let decoded = (input[index] == b'%')
+            .then(|| input.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match decoded {
+            Some(byte) => {
+                output.push(byte);
+                index += 3;
+            }
+            None => {
+                output.push(input[index]);
+                index += 1;
+            }
+        }
+    }
+    output
+}
+
+impl SmithayRunnerState {
+    /// Starts a drag on `surface`, accepting it if it carries files.
+    pub(crate) fn drag_enter(&mut self, data_device: &WlDataDevice, surface: &WlSurface) {
+        self.cancel_file_drag();
+
+        let Some(offer) = data_device
+            .data::<DataDeviceData>()
+            .and_then(|data| data.drag_offer())
+        else {
+            return;
+        };
+        let Some(window) = self.surface_entity(surface) else {
+            offer.accept_mime_type(offer.serial, None);
+            return;
+        };
+        if !offer.with_mime_types(|mime_types| {
+            mime_types
+                .iter()
+                .any(|mime_type| mime_type == URI_LIST_MIME_TYPE)
+        }) {
+            offer.accept_mime_type(offer.serial, None);
+            return;
+        }
+
+        offer.accept_mime_type(offer.serial, Some(URI_LIST_MIME_TYPE.to_string()));
+        offer.set_actions(DndAction::Copy, DndAction::Copy);
+        let read_pipe = match offer.receive(URI_LIST_MIME_TYPE.to_string()) {
+            Ok(read_pipe) => read_pipe,
+            Err(err) => {
+                warn!("failed to receive the dragged files: {}", err);
+                return;
+            }
+        };
+        let data_offer = offer.inner().clone();
+        self.file_drag = Some(FileDrag {
+            window,
+            offer,
+            paths: None,
+            action: DndAction::empty(),
+            dropped: false,
+        });
+        self.receive_pipe(read_pipe, move |state, result| match result {
+            Ok(data) => state.file_drag_received(&data_offer, parse_uri_list(&data)),
+            Err(err) => {
+                warn!("failed to receive the dragged files: {}", err);
+                state.file_drag_received(&data_offer, Vec::new());
+            }
+        });
+    }
+
+    /// Reports the dragged files once they are received.
+    fn file_drag_received(&mut self, data_offer: &WlDataOffer, paths: Vec<PathBuf>) {
+        let Some(file_drag) = self
+            .file_drag
+            .as_mut()
+            .filter(|file_drag| file_drag.offer.inner() == data_offer)
+        else {
+            return;
+        };
+        let window = file_drag.window;
+        for path_buf in &paths {
+            self.bevy_window_events
+                .push(WindowEvent::from(FileDragAndDrop::HoveredFile {
+                    window,
+                    path_buf: path_buf.clone(),
+                }));
+        }
+        file_drag.paths = Some(paths);
+        if file_drag.dropped {
+            self.finish_file_drop();
+        }
+    }
+
+    /// Records the action the compositor selected for the dragged files.
+    pub(crate) fn drag_selected_action(&mut self, offer: &DragOffer, action: DndAction) {
+        if let Some(file_drag) = self
+            .file_drag
+            .as_mut()
+            .filter(|file_drag| file_drag.offer.inner() == offer.inner())
+        {
+            file_drag.action = action;
+        }
+    }
+
+    /// Drops the dragged files on the window, once they are received.
+    pub(crate) fn drag_drop(&mut self) {
+        let Some(file_drag) = &mut self.file_drag else {
+            return;
+        };
+        file_drag.dropped = true;
+        if file_drag.paths.is_some() {
+            self.finish_file_drop();
+        }
+    }
+
+    /// Ends a drag that left the window without dropping.
+    pub(crate) fn drag_leave(&mut self) {
+        if self
+            .file_drag
+            .as_ref()
+            .is_some_and(|file_drag| !file_drag.dropped)
+        {
+            self.cancel_file_drag();
+        }
+    }
+
+    fn finish_file_drop(&mut self) {
+        let Some(file_drag) = self.file_drag.take() else {
+            return;
+        };
+        for path_buf in file_drag.paths.unwrap_or_default() {
+            self.bevy_window_events
+                .push(WindowEvent::from(FileDragAndDrop::DroppedFile {
+                    window: file_drag.window,
+                    path_buf,
+                }));
+        }
+        // Only a drop with an accepted action may be finished, the others are just destroyed.
+        if !file_drag.action.is_empty() {
+            file_drag.offer.finish();
+        }
+        // Dropped offers are not destroyed on leave.
+        file_drag.offer.destroy();
+    }
+
+    fn cancel_file_drag(&mut self) {
+        let Some(file_drag) = self.file_drag.take() else {
+            return;
+        };
+        if file_drag.paths.is_some_and(|paths| !paths.is_empty()) {
+            self.bevy_window_events
+                .push(WindowEvent::from(FileDragAndDrop::HoveredFileCanceled {
+                    window: file_drag.window,
+                }));
+        }
+        if file_drag.dropped {
+            file_drag.offer.destroy();
+        }
+    }
+
+    /// Starts the drags requested through the [`DragAndDrop`] resource.
+    pub(crate) fn update_drag_sources(&mut self, qh: &QueueHandle<SmithayRunnerState>) {
+        let requests = std::mem::take(&mut self.world_mut().resource_mut::<DragAndDrop>().requests);
+        for (id, drag) in requests {
+            if !self.start_drag(id, drag, qh) {
+                self.world_mut().send_event(DragCancelled { id });
+            }
+        }
+    }
+
+    /// Starts a drag, returning `false` if it could not be started.
+    fn start_drag(
+        &mut self,
+        id: DragId,
+        drag: StartDrag,
+        qh: &QueueHandle<SmithayRunnerState>,
+    ) -> bool {
+        // The compositor only starts a drag during the implicit grab of a press.
+        let Some((seat, serial)) = self.latest_press_serial() else {
+            warn!("a drag can only be started after a pointer button press or touch down");
+            return false;
+        };
+        let Some(origin) = self
+            .world()
+            .non_send_resource::<SmithayWindows>()
+            .wl_surface(drag.window)
+            .cloned()
+        else {
+            warn!(
+                "cannot start a drag from {}, which is not a window",
+                drag.window
+            );
+            return false;
+        };
+        let (Some(manager), Some(data_device)) = (
+            self.clipboard.manager(),
+            self.clipboard.data_device(Some(&seat)),
+        ) else {
+            return false;
+        };
+
+        let actions = drag
+            .actions
+            .iter()
+            .fold(DndAction::empty(), |actions, action| {
+                actions | action.to_dnd_action()
+            });
+        let source = manager.create_drag_and_drop_source(qh, drag.mime_types, actions);
+        let icon = drag
+            .icon
+            .and_then(|icon| self.create_drag_icon(drag.window, &icon, qh));
+        source.start_drag(
+            data_device,
+            &origin,
+            icon.as_ref().map(|icon| &icon.surface),
+            serial,
+        );
+
+        // Replacing the active drag destroys its source, which cancels it.
+        if let Some(previous) = self.drag_source.take() {
+            self.world_mut()
+                .send_event(DragCancelled { id: previous.id });
+        }
+        self.drag_source = Some(ActiveDrag {
+            id,
+            source,
+            data: drag.data,
+            action: None,
+            _icon: icon,
+        });
+        true
+    }
+
+    /// Renders the drag icon into a surface, at the scale of the window the drag starts from.
+    fn create_drag_icon(
+        &self,
+        window: Entity,
+        icon: &DragIcon,
+        qh: &QueueHandle<SmithayRunnerState>,
+    ) -> Option<DragIconSurface> {
+        let world = self.world();
+        let scale = world
+            .get::<Window>(window)
+            .map_or(1, |window| (window.scale_factor().ceil() as i32).max(1));
+        let image = CustomCursorImage {
+            handle: icon.image.clone(),
+            hotspot: icon.hotspot,
+            ..Default::default()
+        };
+        let Some((pixels, size, hotspot)) =
+            render_custom_cursor(&image, scale, world.get_resource::<Assets<Image>>()?, None)
+        else {
+            warn!("the drag icon is not loaded yet");
+            return None;
+        };
+
+        let (width, height) = (size.x as i32, size.y as i32);
+        let mut pool = SlotPool::new(pixels.len(), &self.shm)
+            .inspect_err(|err| warn!("failed to create a shm pool for the drag icon: {}", err))
+            .ok()?;
+        let (buffer, canvas) = pool
+            .create_buffer(width, height, width * 4, wl_shm::Format::Argb8888)
+            .inspect_err(|err| warn!("failed to create the drag icon buffer: {}", err))
+            .ok()?;
+        canvas[..pixels.len()].copy_from_slice(&pixels);
+
+        let compositor = world
+            .non_send_resource::<SmithayWindows>()
+            .compositor
+            .as_ref()?;
+        let surface = compositor.create_surface(qh);
+        surface.set_buffer_scale(scale);
+        // The top-left corner of the icon starts at the pointer, so it is moved by the hotspot.
+        if surface.version() >= 5 {
+            surface.attach(Some(buffer.wl_buffer()), 0, 0);
+            surface.offset(-hotspot.0, -hotspot.1);
+        } else {
+            surface.attach(Some(buffer.wl_buffer()), -hotspot.0, -hotspot.1);
+        }
+        surface.damage_buffer(0, 0, width, height);
+        surface.commit();
+
+        Some(DragIconSurface {
+            surface,
+            _pool: pool,
+            _buffer: buffer,
+        })
+    }
+
+    fn active_drag(&mut self, source: &WlDataSource) -> Option<&mut ActiveDrag> {
+        self.drag_source
+            .as_mut()
+            .filter(|drag| drag.source.inner() == source)
+    }
+
+    /// Sends the dragged data to the drop target.
+    pub(crate) fn send_drag_data(
+        &mut self,
+        source: &WlDataSource,
+        mime_type: &str,
+        write_pipe: WritePipe,
+    ) {
+        if let Some(data) = self
+            .active_drag(source)
+            .and_then(|drag| (drag.data)(mime_type))
+        {
+            self.send_pipe(write_pipe, data);
+        }
+    }
+
+    pub(crate) fn drag_action(&mut self, source: &WlDataSource, action: DndAction) {
+        let Some(drag) = self.active_drag(source) else {
+            return;
+        };
+        let action = DragAction::from_dnd_action(action);
+        if drag.action != action {
+            drag.action = action;
+            let id = drag.id;
+            self.world_mut()
+                .send_event(DragActionChanged { id, action });
+        }
+    }
+
+    pub(crate) fn drag_dropped(&mut self, source: &WlDataSource) {
+        if let Some(drag) = self.active_drag(source) {
+            let id = drag.id;
+            self.world_mut().send_event(DragDropped { id });
+        }
+    }
+
+    pub(crate) fn drag_finished(&mut self, source: &WlDataSource) {
+        if self.active_drag(source).is_none() {
+            return;
+        }
+        if let Some(drag) = self.drag_source.take() {
+            self.world_mut().send_event(DragFinished {
+                id: drag.id,
+                action: drag.action,
+            });
+        }
+    }
+
+    pub(crate) fn drag_cancelled(&mut self, source: &WlDataSource) {
+        if self.active_drag(source).is_none() {
+            return;
+        }
+        if let Some(drag) = self.drag_source.take() {
+            self.world_mut().send_event(DragCancelled { id: drag.id });
+        }
+    }
+}
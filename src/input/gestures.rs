@@ -0,0 +1,229 @@
+use bevy::{
+    input::gestures::{PanGesture, PinchGesture, RotationGesture},
+    math::Vec2,
+    prelude::Entity,
+};
+use smithay_client_toolkit::{
+    globals::GlobalData,
+    reexports::{
+        client::{
+            Connection, Dispatch, Proxy, QueueHandle,
+            globals::GlobalList,
+            protocol::{wl_pointer::WlPointer, wl_seat::WlSeat, wl_surface::WlSurface},
+        },
+        protocols::wp::pointer_gestures::zv1::client::{
+            zwp_pointer_gesture_hold_v1::{self, ZwpPointerGestureHoldV1},
+            zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+            zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+            zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+        },
+    },
+};
+
+use crate::{smithay_windows::SmithayWindows, state::SmithayRunnerState};
+
+/// The touchpad gestures of the pointer of a single seat.
+struct Gestures {
+    seat: WlSeat,
+    swipe: ZwpPointerGestureSwipeV1,
+    pinch: ZwpPointerGesturePinchV1,
+    hold: Option<ZwpPointerGestureHoldV1>,
+    /// The window the gesture in progress started on.
+    focus: Option<Entity>,
+    /// The pinch scale of the previous update, since bevy expects scale deltas.
+    pinch_scale: f64,
+}
+
+impl Drop for Gestures {
+    fn drop(&mut self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+        if let Some(hold) = &self.hold {
+            hold.destroy();
+        }
+    }
+}
+
+pub(crate) struct PointerGesturesState {
+    manager: Option<ZwpPointerGesturesV1>,
+    gestures: Vec<Gestures>,
+}
+
+impl PointerGesturesState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            manager: globals.bind(qh, 1..=3, GlobalData).ok(),
+            gestures: Vec::new(),
+        }
+    }
+
+    /// Creates the gesture objects for the newly attached pointer of `seat`.
+    pub(crate) fn add_pointer(
+        &mut self,
+        seat: &WlSeat,
+        pointer: &WlPointer,
+        qh: &QueueHandle<SmithayRunnerState>,
+    ) {
+        let Some(manager) = &self.manager else {
+            return;
+        };
+        // Hold gestures were added in version 3.
+        let hold = (manager.version() >= 3).then(|| manager.get_hold_gesture(pointer, qh, ()));
+        self.gestures.push(Gestures {
+            seat: seat.clone(),
+            swipe: manager.get_swipe_gesture(pointer, qh, ()),
+            pinch: manager.get_pinch_gesture(pointer, qh, ()),
+            hold,
+            focus: None,
+            pinch_scale: 1.0,
+        });
+    }
+
+    /// Destroys the gesture objects of the removed pointer of `seat`.
+    pub(crate) fn remove_pointer(&mut self, seat: &WlSeat) {
+        self.gestures.retain(|gestures| &gestures.seat != seat);
+    }
+
+    fn find_mut(&mut self, matches: impl Fn(&Gestures) -> bool) -> Option<&mut Gestures> {
+        self.gestures.iter_mut().find(|gestures| matches(gestures))
+    }
+}
+
+impl SmithayRunnerState {
+    fn begin_gesture(&mut self, gestures: impl Fn(&Gestures) -> bool, surface: &WlSurface) {
+        let focus = self
+            .world()
+            .non_send_resource::<SmithayWindows>()
+            .smithay_to_entity
+            .get(&surface.id())
+            .copied();
+        if let Some(gestures) = self.pointer_gestures.find_mut(gestures) {
+            gestures.focus = focus;
+            gestures.pinch_scale = 1.0;
+        }
+    }
+
+    fn end_gesture(&mut self, gestures: impl Fn(&Gestures) -> bool) {
+        if let Some(gestures) = self.pointer_gestures.find_mut(gestures) {
+            gestures.focus = None;
+        }
+    }
+
+    /// Returns the seat of the gesture in progress, if it started on a window.
+    fn gesture_seat(&mut self, gestures: impl Fn(&Gestures) -> bool) -> Option<WlSeat> {
+        self.pointer_gestures
+            .find_mut(gestures)
+            .filter(|gestures| gestures.focus.is_some())
+            .map(|gestures| gestures.seat.clone())
+    }
+}
+
+impl Dispatch<ZwpPointerGesturesV1, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpPointerGesturesV1,
+        _: <ZwpPointerGesturesV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_pointer_gestures_v1 has no events")
+    }
+}
+
+impl Dispatch<ZwpPointerGestureSwipeV1, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        swipe: &ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let matches = |gestures: &Gestures| &gestures.swipe == swipe;
+        match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin { surface, .. } => {
+                state.begin_gesture(matches, &surface);
+            }
+            zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => {
+                if let Some(seat) = state.gesture_seat(matches) {
+                    state.send_seat_input(Some(&seat), PanGesture(Vec2::new(dx as f32, dy as f32)));
+                }
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End { .. } => {
+                state.end_gesture(matches);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerGesturePinchV1, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        pinch: &ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let matches = |gestures: &Gestures| &gestures.pinch == pinch;
+        match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin { surface, .. } => {
+                state.begin_gesture(matches, &surface);
+            }
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                scale, rotation, ..
+            } => {
+                let Some(gestures) = state
+                    .pointer_gestures
+                    .find_mut(matches)
+                    .filter(|gestures| gestures.focus.is_some())
+                else {
+                    return;
+                };
+                let delta = scale - gestures.pinch_scale;
+                gestures.pinch_scale = scale;
+                let seat = gestures.seat.clone();
+                if delta != 0.0 {
+                    state.send_seat_input(Some(&seat), PinchGesture(delta as f32));
+                }
+                // Wayland reports clockwise degrees, bevy expects counterclockwise ones.
+                if rotation != 0.0 {
+                    state.send_seat_input(Some(&seat), RotationGesture(-rotation as f32));
+                }
+            }
+            zwp_pointer_gesture_pinch_v1::Event::End { .. } => {
+                state.end_gesture(matches);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpPointerGestureHoldV1, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        hold: &ZwpPointerGestureHoldV1,
+        event: zwp_pointer_gesture_hold_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let matches = |gestures: &Gestures| gestures.hold.as_ref() == Some(hold);
+        match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin { surface, .. } => {
+                state.begin_gesture(matches, &surface);
+                // Fingers resting on the touchpad stop any pan in progress, e.g. kinetic
+                // scrolling, which is reported as an empty pan.
+                if let Some(seat) = state.gesture_seat(matches) {
+                    state.send_seat_input(Some(&seat), PanGesture(Vec2::ZERO));
+                }
+            }
+            zwp_pointer_gesture_hold_v1::Event::End { .. } => {
+                state.end_gesture(matches);
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,43 +1,182 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::AppSendEvent;
+use crate::input::seat::send_seat_event;
+use crate::smithay_windows::SmithayWindows;
 use crate::state::SmithayRunnerState;
-use crate::{AppSendEvent, smithay_windows::SmithayWindows};
-use bevy::input::keyboard::Key;
+use bevy::input::keyboard::{Key, KeyCode, KeyboardFocusLost, NativeKey, NativeKeyCode};
 use bevy::input::{ButtonState, keyboard::KeyboardInput};
 use bevy::log::warn;
-use bevy::prelude::Entity;
+use bevy::prelude::{Entity, Resource, World};
+use bevy::window::{Window, WindowEvent, WindowFocused};
 use smithay_client_toolkit::{
     delegate_keyboard,
-    reexports::client::Proxy,
-    seat::keyboard::{KeyEvent, KeyboardHandler, Keysym},
+    reexports::client::{
+        Proxy,
+        backend::ObjectId,
+        protocol::{wl_keyboard::WlKeyboard, wl_surface::WlSurface},
+    },
+    seat::keyboard::{KeyEvent, KeyboardData, KeyboardHandler, Keysym, Modifiers},
 };
 
+/// The keyboard modifiers and lock keys currently active, as reported by the compositor.
+///
+/// All modifiers are cleared while no window has keyboard focus.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyboardModifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// The "logo" key, also known as the "super" or "windows" key.
+    pub logo: bool,
+    pub caps_lock: bool,
+    pub num_lock: bool,
+}
+
+impl From<Modifiers> for KeyboardModifiers {
+    fn from(modifiers: Modifiers) -> Self {
+        Self {
+            ctrl: modifiers.ctrl,
+            alt: modifiers.alt,
+            shift: modifiers.shift,
+            logo: modifiers.logo,
+            caps_lock: modifiers.caps_lock,
+            num_lock: modifiers.num_lock,
+        }
+    }
+}
+
 /// Converts a Smithay keyboard event to a Bevy keyboard input event.
+// Key text is a `SmolStr` or a `String` depending on the features of `bevy_input`.
+#[allow(clippy::useless_conversion)]
 fn convert_keyboard_event(
     event: KeyEvent,
     entity: Entity,
     state: ButtonState,
+    repeat: bool,
 ) -> bevy::input::keyboard::KeyboardInput {
+    // The text is composed by xkb, so dead keys and compose sequences only produce text once the
+    // sequence is complete.
+    let text = match state {
+        ButtonState::Pressed => event.utf8.filter(|text| !text.is_empty()).map(Into::into),
+        ButtonState::Released => None,
+    };
+
     KeyboardInput {
         state,
-        text: None,
+        text,
         window: entity,
-        key_code: convert_to_key_code(event.keysym),
+        key_code: convert_to_key_code(event.raw_code),
         logical_key: convert_to_logical_key(event.keysym),
-        repeat: false,
+        repeat,
     }
 }
 
 /// Converts a Smithay Keysym to a Bevy Key.
-fn convert_to_logical_key(keysym: Keysym) -> bevy::input::keyboard::Key {
-    // First, attempt to get the character representation based on keyboard layout.
+///
+/// Named keys take precedence over their character representation, so that e.g. Return and
+/// BackSpace are reported as [`Key::Enter`] and [`Key::Backspace`] like with winit. Keysyms
+/// without a Bevy equivalent are reported as [`Key::Unidentified`].
+// Key text is a `SmolStr` or a `String` depending on the features of `bevy_input`.
+#[allow(clippy::useless_conversion)]
+fn convert_to_logical_key(keysym: Keysym) -> Key {
+    if let Some(key) = convert_to_named_key(keysym) {
+        return key;
+    }
+    if (Keysym::dead_grave..=Keysym::dead_longsolidusoverlay).contains(&keysym) {
+        return Key::Dead(convert_dead_key(keysym));
+    }
     if let Some(c) = keysym.key_char() {
-        // Use Key::Character for printable chars. Convert to SmolStr.
-        return Key::Character((c.to_string()).into());
+        return Key::Character(c.to_string().into());
     }
 
-    // If key_char() returned None, it's a non-printable key (Control, Function, Arrow etc.)
-    // Map the Keysym variant to the corresponding Bevy Key variant.
-    match keysym {
-        // Function Keys
+    match keysym.raw() {
+        0 => Key::Unidentified(NativeKey::Unidentified),
+        raw => Key::Unidentified(NativeKey::Xkb(raw)),
+    }
+}
+
+/// Converts a keysym of a key without a character to a named Bevy Key, following winit.
+fn convert_to_named_key(keysym: Keysym) -> Option<Key> {
+    Some(match keysym {
+        // TTY function keys
+        Keysym::BackSpace => Key::Backspace,
+        Keysym::Tab => Key::Tab,
+        Keysym::Clear => Key::Clear,
+        Keysym::Return => Key::Enter,
+        Keysym::Pause => Key::Pause,
+        Keysym::Scroll_Lock => Key::ScrollLock,
+        Keysym::Sys_Req => Key::PrintScreen,
+        Keysym::Escape => Key::Escape,
+        Keysym::Delete => Key::Delete,
+
+        // IME keys
+        Keysym::Multi_key => Key::Compose,
+        Keysym::Codeinput => Key::CodeInput,
+        Keysym::SingleCandidate => Key::SingleCandidate,
+        Keysym::MultipleCandidate => Key::AllCandidates,
+        Keysym::PreviousCandidate => Key::PreviousCandidate,
+
+        // Japanese keys
+        Keysym::Kanji => Key::KanjiMode,
+        Keysym::Muhenkan => Key::NonConvert,
+        Keysym::Henkan_Mode => Key::Convert,
+        Keysym::Romaji => Key::Romaji,
+        Keysym::Hiragana => Key::Hiragana,
+        Keysym::Hiragana_Katakana => Key::HiraganaKatakana,
+        Keysym::Zenkaku => Key::Zenkaku,
+        Keysym::Hankaku => Key::Hankaku,
+        Keysym::Zenkaku_Hankaku => Key::ZenkakuHankaku,
+        Keysym::Kana_Lock => Key::KanaMode,
+        Keysym::Kana_Shift => Key::KanaMode,
+        Keysym::Eisu_Shift => Key::Alphanumeric,
+        Keysym::Eisu_toggle => Key::Alphanumeric,
+
+        // Cursor control & motion
+        Keysym::Home => Key::Home,
+        Keysym::Left => Key::ArrowLeft,
+        Keysym::Up => Key::ArrowUp,
+        Keysym::Right => Key::ArrowRight,
+        Keysym::Down => Key::ArrowDown,
+        Keysym::Page_Up => Key::PageUp,
+        Keysym::Page_Down => Key::PageDown,
+        Keysym::End => Key::End,
+
+        // Misc. functions
+        Keysym::Select => Key::Select,
+        Keysym::Print => Key::PrintScreen,
+        Keysym::Execute => Key::Execute,
+        Keysym::Insert => Key::Insert,
+        Keysym::Undo => Key::Undo,
+        Keysym::Redo => Key::Redo,
+        Keysym::Menu => Key::ContextMenu,
+        Keysym::Find => Key::Find,
+        Keysym::Cancel => Key::Cancel,
+        Keysym::Help => Key::Help,
+        Keysym::Break => Key::Pause,
+        Keysym::Mode_switch => Key::ModeChange,
+        Keysym::Num_Lock => Key::NumLock,
+
+        // Keypad keys
+        Keysym::KP_Tab => Key::Tab,
+        Keysym::KP_Enter => Key::Enter,
+        Keysym::KP_F1 => Key::F1,
+        Keysym::KP_F2 => Key::F2,
+        Keysym::KP_F3 => Key::F3,
+        Keysym::KP_F4 => Key::F4,
+        Keysym::KP_Home => Key::Home,
+        Keysym::KP_Left => Key::ArrowLeft,
+        Keysym::KP_Up => Key::ArrowUp,
+        Keysym::KP_Right => Key::ArrowRight,
+        Keysym::KP_Down => Key::ArrowDown,
+        Keysym::KP_Page_Up => Key::PageUp,
+        Keysym::KP_Page_Down => Key::PageDown,
+        Keysym::KP_End => Key::End,
+        // This is the key labeled "5" on the numpad when NumLock is off.
+        Keysym::KP_Insert => Key::Insert,
+        Keysym::KP_Delete => Key::Delete,
+
+        // Function keys
         Keysym::F1 => Key::F1,
         Keysym::F2 => Key::F2,
         Keysym::F3 => Key::F3,
@@ -62,295 +201,559 @@ fn convert_to_logical_key(keysym: Keysym) -> bevy::input::keyboard::Key {
         Keysym::F22 => Key::F22,
         Keysym::F23 => Key::F23,
         Keysym::F24 => Key::F24,
-
-        // Control & Navigation
-        Keysym::Escape => Key::Escape,
-        Keysym::Pause => Key::Pause,
-        Keysym::Scroll_Lock => Key::ScrollLock,
-        Keysym::Print => Key::PrintScreen,
-        Keysym::Insert => Key::Insert,
-        Keysym::Delete => Key::Delete,
-        Keysym::Home => Key::Home,
-        Keysym::End => Key::End,
-        Keysym::Page_Up => Key::PageUp,
-        Keysym::Page_Down => Key::PageDown,
-        Keysym::BackSpace => Key::Backspace,
-        Keysym::Return | Keysym::KP_Enter => Key::Enter, // Map both main and numpad Enter
-        Keysym::Tab => Key::Tab,
-        Keysym::Menu => Key::ContextMenu,
-
-        // Arrow Keys
-        Keysym::Left => Key::ArrowLeft,
-        Keysym::Right => Key::ArrowRight,
-        Keysym::Up => Key::ArrowUp,
-        Keysym::Down => Key::ArrowDown,
+        Keysym::F25 => Key::F25,
+        Keysym::F26 => Key::F26,
+        Keysym::F27 => Key::F27,
+        Keysym::F28 => Key::F28,
+        Keysym::F29 => Key::F29,
+        Keysym::F30 => Key::F30,
+        Keysym::F31 => Key::F31,
+        Keysym::F32 => Key::F32,
+        Keysym::F33 => Key::F33,
+        Keysym::F34 => Key::F34,
+        Keysym::F35 => Key::F35,
 
         // Modifiers
         Keysym::Shift_L => Key::Shift,
         Keysym::Shift_R => Key::Shift,
         Keysym::Control_L => Key::Control,
         Keysym::Control_R => Key::Control,
+        Keysym::Caps_Lock => Key::CapsLock,
+
         Keysym::Alt_L => Key::Alt,
         Keysym::Alt_R => Key::Alt,
-        Keysym::Super_L | Keysym::Meta_L => Key::Super, // Combine Super/Meta
-        Keysym::Super_R | Keysym::Meta_R => Key::Super,
-        Keysym::Caps_Lock => Key::CapsLock,
-        Keysym::Num_Lock => Key::NumLock,
+        Keysym::Meta_L => Key::Super,
+        Keysym::Super_L => Key::Super,
+        Keysym::Meta_R => Key::Super,
+        Keysym::Super_R => Key::Super,
+        Keysym::Hyper_L => Key::Hyper,
+        Keysym::Hyper_R => Key::Hyper,
+
+        // XKB function and modifier keys
+        Keysym::ISO_Level3_Shift => Key::AltGraph,
+        Keysym::ISO_Level3_Latch => Key::AltGraph,
+        Keysym::ISO_Level3_Lock => Key::AltGraph,
+        Keysym::ISO_Next_Group => Key::GroupNext,
+        Keysym::ISO_Prev_Group => Key::GroupPrevious,
+        Keysym::ISO_First_Group => Key::GroupFirst,
+        Keysym::ISO_Last_Group => Key::GroupLast,
+        Keysym::ISO_Left_Tab => Key::Tab,
+        Keysym::ISO_Enter => Key::Enter,
+
+        // 3270 terminal keys
+        Keysym::_3270_EraseEOF => Key::EraseEof,
+        Keysym::_3270_Attn => Key::Attn,
+        Keysym::_3270_Play => Key::Play,
+        Keysym::_3270_ExSelect => Key::ExSel,
+        Keysym::_3270_CursorSelect => Key::CrSel,
+        Keysym::_3270_PrintScreen => Key::PrintScreen,
+        Keysym::_3270_Enter => Key::Enter,
+
+        Keysym::space => Key::Space,
+
+        // XFree86 - Backlight controls
+        Keysym::XF86_MonBrightnessUp => Key::BrightnessUp,
+        Keysym::XF86_MonBrightnessDown => Key::BrightnessDown,
+
+        // XFree86 - "Internet"
+        Keysym::XF86_Standby => Key::Standby,
+        Keysym::XF86_AudioLowerVolume => Key::AudioVolumeDown,
+        Keysym::XF86_AudioRaiseVolume => Key::AudioVolumeUp,
+        Keysym::XF86_AudioPlay => Key::MediaPlayPause,
+        Keysym::XF86_AudioStop => Key::MediaStop,
+        Keysym::XF86_AudioPrev => Key::MediaTrackPrevious,
+        Keysym::XF86_AudioNext => Key::MediaTrackNext,
+        Keysym::XF86_HomePage => Key::BrowserHome,
+        Keysym::XF86_Mail => Key::LaunchMail,
+        Keysym::XF86_Search => Key::BrowserSearch,
+        Keysym::XF86_Stop => Key::BrowserStop,
+        Keysym::XF86_AudioRecord => Key::MediaRecord,
+
+        // XFree86 - PDA
+        Keysym::XF86_Calculator => Key::LaunchApplication2,
+        Keysym::XF86_Calendar => Key::LaunchCalendar,
+        Keysym::XF86_PowerDown => Key::Power,
+
+        // XFree86 - More "Internet"
+        Keysym::XF86_Back => Key::BrowserBack,
+        Keysym::XF86_Forward => Key::BrowserForward,
+        Keysym::XF86_Refresh => Key::BrowserRefresh,
+        Keysym::XF86_PowerOff => Key::Power,
+        Keysym::XF86_WakeUp => Key::WakeUp,
+        Keysym::XF86_Eject => Key::Eject,
+        Keysym::XF86_ScreenSaver => Key::LaunchScreenSaver,
+        Keysym::XF86_WWW => Key::LaunchWebBrowser,
+        Keysym::XF86_Sleep => Key::Standby,
+        Keysym::XF86_Favorites => Key::BrowserFavorites,
+        Keysym::XF86_AudioPause => Key::MediaPause,
+        Keysym::XF86_MyComputer => Key::LaunchApplication1,
+        Keysym::XF86_AudioRewind => Key::MediaRewind,
+
+        Keysym::XF86_Calculater => Key::LaunchApplication2,
+        Keysym::XF86_Close => Key::Close,
+        Keysym::XF86_Copy => Key::Copy,
+        Keysym::XF86_Cut => Key::Cut,
+        Keysym::XF86_Excel => Key::LaunchSpreadsheet,
+        Keysym::XF86_LogOff => Key::LogOff,
+        Keysym::XF86_MySites => Key::BrowserFavorites,
+        Keysym::XF86_New => Key::New,
+        Keysym::XF86_Open => Key::Open,
+        Keysym::XF86_Paste => Key::Paste,
+        Keysym::XF86_Phone => Key::LaunchPhone,
+        Keysym::XF86_Reply => Key::MailReply,
+        Keysym::XF86_Reload => Key::BrowserRefresh,
+        Keysym::XF86_Save => Key::Save,
+        Keysym::XF86_Send => Key::MailSend,
+        Keysym::XF86_Spell => Key::SpellCheck,
+        Keysym::XF86_SplitScreen => Key::SplitScreenToggle,
+        Keysym::XF86_Video => Key::LaunchMediaPlayer,
+        Keysym::XF86_Word => Key::LaunchWordProcessor,
+        Keysym::XF86_ZoomIn => Key::ZoomIn,
+        Keysym::XF86_ZoomOut => Key::ZoomOut,
+
+        Keysym::XF86_WebCam => Key::LaunchWebCam,
+        Keysym::XF86_MailForward => Key::MailForward,
+        Keysym::XF86_Music => Key::LaunchMusicPlayer,
+
+        Keysym::XF86_AudioForward => Key::MediaFastForward,
+        Keysym::XF86_AudioRandomPlay => Key::RandomToggle,
+        Keysym::XF86_Subtitle => Key::Subtitle,
+        Keysym::XF86_AudioCycleTrack => Key::MediaAudioTrack,
+        Keysym::XF86_Suspend => Key::Standby,
+        Keysym::XF86_Hibernate => Key::Hibernate,
+        Keysym::XF86_AudioMute => Key::AudioVolumeMute,
+        Keysym::XF86_AudioMicMute => Key::MicrophoneVolumeMute,
+
+        Keysym::XF86_Next_VMode => Key::VideoModeNext,
+
+        Keysym::SUN_Copy => Key::Copy,
+        Keysym::SUN_Open => Key::Open,
+        Keysym::SUN_Paste => Key::Paste,
+        Keysym::SUN_Cut => Key::Cut,
+
+        Keysym::SUN_AudioLowerVolume => Key::AudioVolumeDown,
+        Keysym::SUN_AudioMute => Key::AudioVolumeMute,
+        Keysym::SUN_AudioRaiseVolume => Key::AudioVolumeUp,
+        Keysym::SUN_VideoLowerBrightness => Key::BrightnessDown,
+        Keysym::SUN_VideoRaiseBrightness => Key::BrightnessUp,
 
-        // Keys that *might* have produced a character but key_char() returned None.
-        // This could happen for dead keys, IME input, or if key_char() implementation is limited.
-        // We need a fallback. Panicking with todo!() highlights these cases during development.
-        // A production system might need more robust handling (e.g., logging, ignoring, specific Key variant if available).
-        Keysym::A
-        | Keysym::B
-        | Keysym::C
-        | Keysym::D
-        | Keysym::E
-        | Keysym::F
-        | Keysym::G
-        | Keysym::H
-        | Keysym::I
-        | Keysym::J
-        | Keysym::K
-        | Keysym::L
-        | Keysym::M
-        | Keysym::N
-        | Keysym::O
-        | Keysym::P
-        | Keysym::Q
-        | Keysym::R
-        | Keysym::S
-        | Keysym::T
-        | Keysym::U
-        | Keysym::V
-        | Keysym::W
-        | Keysym::X
-        | Keysym::Y
-        | Keysym::Z
-        | Keysym::_0
-        | Keysym::_1
-        | Keysym::_2
-        | Keysym::_3
-        | Keysym::_4
-        | Keysym::_5
-        | Keysym::_6
-        | Keysym::_7
-        | Keysym::_8
-        | Keysym::_9
-        | Keysym::grave
-        | Keysym::minus
-        | Keysym::equal
-        | Keysym::bracketleft
-        | Keysym::bracketright
-        | Keysym::backslash
-        | Keysym::semicolon
-        | Keysym::apostrophe
-        | Keysym::comma
-        | Keysym::period
-        | Keysym::slash
-        | Keysym::space => {
-            warn!(
-                "Keysym {:?} did not produce a character via key_char(). This might be unexpected.",
-                keysym
-            );
-            // Bevy's Key enum doesn't have a generic 'Unidentified' or 'Unknown' variant.
-            // Panicking via todo!() helps catch these during development.
-            // You might need custom logic or decide to ignore these cases in production.
-            todo!(
-                "Unhandled case: Keysym {:?} did not yield a char via key_char(). How to map to bevy::input::keyboard::Key?",
-                keysym
-            )
+        _ => return None,
+    })
+}
+
+/// Returns the character a dead key adds to the following key, if it has a spacing form.
+fn convert_dead_key(keysym: Keysym) -> Option<char> {
+    Some(match keysym {
+        Keysym::dead_grave => '`',
+        Keysym::dead_acute => '´',
+        Keysym::dead_circumflex => '^',
+        Keysym::dead_tilde => '~',
+        Keysym::dead_macron => '¯',
+        Keysym::dead_breve => '˘',
+        Keysym::dead_abovedot => '˙',
+        Keysym::dead_diaeresis => '¨',
+        Keysym::dead_abovering => '˚',
+        Keysym::dead_doubleacute => '˝',
+        Keysym::dead_caron => 'ˇ',
+        Keysym::dead_cedilla => '¸',
+        Keysym::dead_ogonek => '˛',
+        Keysym::dead_iota => 'ͺ',
+        _ => return None,
+    })
+}
+
+/// Converts an evdev keycode to a Bevy KeyCode.
+///
+/// The physical key does not depend on the keyboard layout, so this follows the evdev codes from
+/// linux/input-event-codes.h instead of the keysym, using the same table as winit.
+fn convert_to_key_code(raw_code: u32) -> KeyCode {
+    match raw_code {
+        0 => KeyCode::Unidentified(NativeKeyCode::Xkb(0)),
+        1 => KeyCode::Escape,
+        2 => KeyCode::Digit1,
+        3 => KeyCode::Digit2,
+        4 => KeyCode::Digit3,
+        5 => KeyCode::Digit4,
+        6 => KeyCode::Digit5,
+        7 => KeyCode::Digit6,
+        8 => KeyCode::Digit7,
+        9 => KeyCode::Digit8,
+        10 => KeyCode::Digit9,
+        11 => KeyCode::Digit0,
+        12 => KeyCode::Minus,
+        13 => KeyCode::Equal,
+        14 => KeyCode::Backspace,
+        15 => KeyCode::Tab,
+        16 => KeyCode::KeyQ,
+        17 => KeyCode::KeyW,
+        18 => KeyCode::KeyE,
+        19 => KeyCode::KeyR,
+        20 => KeyCode::KeyT,
+        21 => KeyCode::KeyY,
+        22 => KeyCode::KeyU,
+        23 => KeyCode::KeyI,
+        24 => KeyCode::KeyO,
+        25 => KeyCode::KeyP,
+        26 => KeyCode::BracketLeft,
+        27 => KeyCode::BracketRight,
+        28 => KeyCode::Enter,
+        29 => KeyCode::ControlLeft,
+        30 => KeyCode::KeyA,
+        31 => KeyCode::KeyS,
+        32 => KeyCode::KeyD,
+        33 => KeyCode::KeyF,
+        34 => KeyCode::KeyG,
+        35 => KeyCode::KeyH,
+        36 => KeyCode::KeyJ,
+        37 => KeyCode::KeyK,
+        38 => KeyCode::KeyL,
+        39 => KeyCode::Semicolon,
+        40 => KeyCode::Quote,
+        41 => KeyCode::Backquote,
+        42 => KeyCode::ShiftLeft,
+        43 => KeyCode::Backslash,
+        44 => KeyCode::KeyZ,
+        45 => KeyCode::KeyX,
+        46 => KeyCode::KeyC,
+        47 => KeyCode::KeyV,
+        48 => KeyCode::KeyB,
+        49 => KeyCode::KeyN,
+        50 => KeyCode::KeyM,
+        51 => KeyCode::Comma,
+        52 => KeyCode::Period,
+        53 => KeyCode::Slash,
+        54 => KeyCode::ShiftRight,
+        55 => KeyCode::NumpadMultiply,
+        56 => KeyCode::AltLeft,
+        57 => KeyCode::Space,
+        58 => KeyCode::CapsLock,
+        59 => KeyCode::F1,
+        60 => KeyCode::F2,
+        61 => KeyCode::F3,
+        62 => KeyCode::F4,
+        63 => KeyCode::F5,
+        64 => KeyCode::F6,
+        65 => KeyCode::F7,
+        66 => KeyCode::F8,
+        67 => KeyCode::F9,
+        68 => KeyCode::F10,
+        69 => KeyCode::NumLock,
+        70 => KeyCode::ScrollLock,
+        71 => KeyCode::Numpad7,
+        72 => KeyCode::Numpad8,
+        73 => KeyCode::Numpad9,
+        74 => KeyCode::NumpadSubtract,
+        75 => KeyCode::Numpad4,
+        76 => KeyCode::Numpad5,
+        77 => KeyCode::Numpad6,
+        78 => KeyCode::NumpadAdd,
+        79 => KeyCode::Numpad1,
+        80 => KeyCode::Numpad2,
+        81 => KeyCode::Numpad3,
+        82 => KeyCode::Numpad0,
+        83 => KeyCode::NumpadDecimal,
+        85 => KeyCode::Lang5,
+        86 => KeyCode::IntlBackslash,
+        87 => KeyCode::F11,
+        88 => KeyCode::F12,
+        89 => KeyCode::IntlRo,
+        90 => KeyCode::Lang3,
+        91 => KeyCode::Lang4,
+        92 => KeyCode::Convert,
+        93 => KeyCode::KanaMode,
+        94 => KeyCode::NonConvert,
+        96 => KeyCode::NumpadEnter,
+        97 => KeyCode::ControlRight,
+        98 => KeyCode::NumpadDivide,
+        99 => KeyCode::PrintScreen,
+        100 => KeyCode::AltRight,
+        102 => KeyCode::Home,
+        103 => KeyCode::ArrowUp,
+        104 => KeyCode::PageUp,
+        105 => KeyCode::ArrowLeft,
+        106 => KeyCode::ArrowRight,
+        107 => KeyCode::End,
+        108 => KeyCode::ArrowDown,
+        109 => KeyCode::PageDown,
+        110 => KeyCode::Insert,
+        111 => KeyCode::Delete,
+        113 => KeyCode::AudioVolumeMute,
+        114 => KeyCode::AudioVolumeDown,
+        115 => KeyCode::AudioVolumeUp,
+        117 => KeyCode::NumpadEqual,
+        119 => KeyCode::Pause,
+        121 => KeyCode::NumpadComma,
+        122 => KeyCode::Lang1,
+        123 => KeyCode::Lang2,
+        124 => KeyCode::IntlYen,
+        125 => KeyCode::SuperLeft,
+        126 => KeyCode::SuperRight,
+        127 => KeyCode::ContextMenu,
+        163 => KeyCode::MediaTrackNext,
+        164 => KeyCode::MediaPlayPause,
+        165 => KeyCode::MediaTrackPrevious,
+        166 => KeyCode::MediaStop,
+        183 => KeyCode::F13,
+        184 => KeyCode::F14,
+        185 => KeyCode::F15,
+        186 => KeyCode::F16,
+        187 => KeyCode::F17,
+        188 => KeyCode::F18,
+        189 => KeyCode::F19,
+        190 => KeyCode::F20,
+        191 => KeyCode::F21,
+        192 => KeyCode::F22,
+        193 => KeyCode::F23,
+        194 => KeyCode::F24,
+        240 => KeyCode::Unidentified(NativeKeyCode::Unidentified),
+        _ => KeyCode::Unidentified(NativeKeyCode::Xkb(raw_code)),
+    }
+}
+
+/// Creates a key event for a key that was pressed or released while the window was not focused.
+fn synthesized_keyboard_event(
+    raw_code: u32,
+    keysym: Keysym,
+    entity: Entity,
+    state: ButtonState,
+) -> KeyboardInput {
+    KeyboardInput {
+        state,
+        text: None,
+        window: entity,
+        key_code: convert_to_key_code(raw_code),
+        logical_key: convert_to_logical_key(keysym),
+        repeat: false,
+    }
+}
+
+/// The keyboard focus of a single seat.
+struct KeyboardFocus<S> {
+    surface: S,
+    /// Keys pressed while the surface is focused, released again when the focus leaves.
+    pressed_keys: Vec<(u32, Keysym)>,
+}
+
+/// Where the keyboard focus sends the events of a single keyboard.
+pub(crate) struct KeyboardEvents<'a, S> {
+    pub(crate) world: &'a mut World,
+    pub(crate) window_events: &'a mut Vec<WindowEvent>,
+    /// The seat of the keyboard, named by the [`SeatInput`](crate::prelude::SeatInput) of each key.
+    pub(crate) seat: Option<Entity>,
+    /// Returns the window of a surface, or `None` if the window was despawned.
+    pub(crate) resolve: fn(&World, &S) -> Option<Entity>,
+}
+
+impl<S> KeyboardEvents<'_, S> {
+    fn entity(&self, surface: &S) -> Option<Entity> {
+        (self.resolve)(self.world, surface)
+    }
+
+    fn send_key(&mut self, event: KeyboardInput) {
+        send_seat_event(self.world, self.window_events, self.seat, event);
+    }
+
+    /// Updates [`Window::focused`] and sends the matching [`WindowFocused`].
+    fn set_window_focused(&mut self, entity: Entity, focused: bool) {
+        if let Some(mut window) = self.world.get_mut::<Window>(entity) {
+            window.focused = focused;
         }
+        self.window_events.send(WindowFocused {
+            window: entity,
+            focused,
+        });
+    }
+}
 
-        // Catch-all for any other Keysym variants not handled above.
-        _ => {
-            todo!("Unhandled keysym variant: {:?}", keysym)
+/// The keyboard focus of every keyboard, keyed by `K` and focusing surfaces `S`.
+///
+/// Surfaces are resolved to windows whenever an event is sent, as windows can be despawned while
+/// they are focused.
+pub(crate) struct KeyboardFocuses<K, S> {
+    focuses: HashMap<K, KeyboardFocus<S>>,
+}
+
+impl<K, S> Default for KeyboardFocuses<K, S> {
+    fn default() -> Self {
+        Self {
+            focuses: HashMap::new(),
         }
     }
 }
 
-/// Converts a Smithay Keysym to a Bevy KeyCode.
-fn convert_to_key_code(keysym: Keysym) -> bevy::prelude::KeyCode {
-    match keysym {
-        // Letters
-        Keysym::A => bevy::prelude::KeyCode::KeyA,
-        Keysym::B => bevy::prelude::KeyCode::KeyB,
-        Keysym::C => bevy::prelude::KeyCode::KeyC,
-        Keysym::D => bevy::prelude::KeyCode::KeyD,
-        Keysym::E => bevy::prelude::KeyCode::KeyE,
-        Keysym::F => bevy::prelude::KeyCode::KeyF,
-        Keysym::G => bevy::prelude::KeyCode::KeyG,
-        Keysym::H => bevy::prelude::KeyCode::KeyH,
-        Keysym::I => bevy::prelude::KeyCode::KeyI,
-        Keysym::J => bevy::prelude::KeyCode::KeyJ,
-        Keysym::K => bevy::prelude::KeyCode::KeyK,
-        Keysym::L => bevy::prelude::KeyCode::KeyL,
-        Keysym::M => bevy::prelude::KeyCode::KeyM,
-        Keysym::N => bevy::prelude::KeyCode::KeyN,
-        Keysym::O => bevy::prelude::KeyCode::KeyO,
-        Keysym::P => bevy::prelude::KeyCode::KeyP,
-        Keysym::Q => bevy::prelude::KeyCode::KeyQ,
-        Keysym::R => bevy::prelude::KeyCode::KeyR,
-        Keysym::S => bevy::prelude::KeyCode::KeyS,
-        Keysym::T => bevy::prelude::KeyCode::KeyT,
-        Keysym::U => bevy::prelude::KeyCode::KeyU,
-        Keysym::V => bevy::prelude::KeyCode::KeyV,
-        Keysym::W => bevy::prelude::KeyCode::KeyW,
-        Keysym::X => bevy::prelude::KeyCode::KeyX,
-        Keysym::Y => bevy::prelude::KeyCode::KeyY,
-        Keysym::Z => bevy::prelude::KeyCode::KeyZ,
-
-        Keysym::a => bevy::prelude::KeyCode::KeyA,
-        Keysym::b => bevy::prelude::KeyCode::KeyB,
-        Keysym::c => bevy::prelude::KeyCode::KeyC,
-        Keysym::d => bevy::prelude::KeyCode::KeyD,
-        Keysym::e => bevy::prelude::KeyCode::KeyE,
-        Keysym::f => bevy::prelude::KeyCode::KeyF,
-        Keysym::g => bevy::prelude::KeyCode::KeyG,
-        Keysym::h => bevy::prelude::KeyCode::KeyH,
-        Keysym::i => bevy::prelude::KeyCode::KeyI,
-        Keysym::j => bevy::prelude::KeyCode::KeyJ,
-        Keysym::k => bevy::prelude::KeyCode::KeyK,
-        Keysym::l => bevy::prelude::KeyCode::KeyL,
-        Keysym::m => bevy::prelude::KeyCode::KeyM,
-        Keysym::n => bevy::prelude::KeyCode::KeyN,
-        Keysym::o => bevy::prelude::KeyCode::KeyO,
-        Keysym::p => bevy::prelude::KeyCode::KeyP,
-        Keysym::q => bevy::prelude::KeyCode::KeyQ,
-        Keysym::r => bevy::prelude::KeyCode::KeyR,
-        Keysym::s => bevy::prelude::KeyCode::KeyS,
-        Keysym::t => bevy::prelude::KeyCode::KeyT,
-        Keysym::u => bevy::prelude::KeyCode::KeyU,
-        Keysym::v => bevy::prelude::KeyCode::KeyV,
-        Keysym::w => bevy::prelude::KeyCode::KeyW,
-        Keysym::x => bevy::prelude::KeyCode::KeyX,
-        Keysym::y => bevy::prelude::KeyCode::KeyY,
-        Keysym::z => bevy::prelude::KeyCode::KeyZ,
-
-        // Numbers Row (might be Keysym::0, Keysym::1 etc. in some libs)
-        Keysym::_0 => bevy::prelude::KeyCode::Digit0,
-        Keysym::_1 => bevy::prelude::KeyCode::Digit1,
-        Keysym::_2 => bevy::prelude::KeyCode::Digit2,
-        Keysym::_3 => bevy::prelude::KeyCode::Digit3,
-        Keysym::_4 => bevy::prelude::KeyCode::Digit4,
-        Keysym::_5 => bevy::prelude::KeyCode::Digit5,
-        Keysym::_6 => bevy::prelude::KeyCode::Digit6,
-        Keysym::_7 => bevy::prelude::KeyCode::Digit7,
-        Keysym::_8 => bevy::prelude::KeyCode::Digit8,
-        Keysym::_9 => bevy::prelude::KeyCode::Digit9,
-
-        // Function Keys
-        Keysym::F1 => bevy::prelude::KeyCode::F1,
-        Keysym::F2 => bevy::prelude::KeyCode::F2,
-        Keysym::F3 => bevy::prelude::KeyCode::F3,
-        Keysym::F4 => bevy::prelude::KeyCode::F4,
-        Keysym::F5 => bevy::prelude::KeyCode::F5,
-        Keysym::F6 => bevy::prelude::KeyCode::F6,
-        Keysym::F7 => bevy::prelude::KeyCode::F7,
-        Keysym::F8 => bevy::prelude::KeyCode::F8,
-        Keysym::F9 => bevy::prelude::KeyCode::F9,
-        Keysym::F10 => bevy::prelude::KeyCode::F10,
-        Keysym::F11 => bevy::prelude::KeyCode::F11,
-        Keysym::F12 => bevy::prelude::KeyCode::F12,
-        Keysym::F13 => bevy::prelude::KeyCode::F13,
-        Keysym::F14 => bevy::prelude::KeyCode::F14,
-        Keysym::F15 => bevy::prelude::KeyCode::F15,
-        Keysym::F16 => bevy::prelude::KeyCode::F16,
-        Keysym::F17 => bevy::prelude::KeyCode::F17,
-        Keysym::F18 => bevy::prelude::KeyCode::F18,
-        Keysym::F19 => bevy::prelude::KeyCode::F19,
-        Keysym::F20 => bevy::prelude::KeyCode::F20,
-        Keysym::F21 => bevy::prelude::KeyCode::F21,
-        Keysym::F22 => bevy::prelude::KeyCode::F22,
-        Keysym::F23 => bevy::prelude::KeyCode::F23,
-        Keysym::F24 => bevy::prelude::KeyCode::F24,
-
-        // Arrow Keys
-        Keysym::Left => bevy::prelude::KeyCode::ArrowLeft,
-        Keysym::Right => bevy::prelude::KeyCode::ArrowRight,
-        Keysym::Up => bevy::prelude::KeyCode::ArrowUp,
-        Keysym::Down => bevy::prelude::KeyCode::ArrowDown,
+impl<K: Eq + Hash + Clone, S> KeyboardFocuses<K, S> {
+    /// Moves the focus of `keyboard` to `surface`, on which `pressed_keys` are already held.
+    pub(crate) fn enter(
+        &mut self,
+        events: &mut KeyboardEvents<S>,
+        keyboard: K,
+        surface: S,
+        pressed_keys: Vec<(u32, Keysym)>,
+    ) {
+        // A missing leave, e.g. for a destroyed surface, must not leave keys stuck.
+        self.leave(events, &keyboard);
 
-        // Modifiers
-        Keysym::Shift_L => bevy::prelude::KeyCode::ShiftLeft,
-        Keysym::Shift_R => bevy::prelude::KeyCode::ShiftRight,
-        Keysym::Control_L => bevy::prelude::KeyCode::ControlLeft,
-        Keysym::Control_R => bevy::prelude::KeyCode::ControlRight,
-        Keysym::Alt_L => bevy::prelude::KeyCode::AltLeft,
-        Keysym::Alt_R => bevy::prelude::KeyCode::AltRight,
-        Keysym::Super_L | Keysym::Meta_L => bevy::prelude::KeyCode::SuperLeft, // Handle Super/Meta variations
-        Keysym::Super_R | Keysym::Meta_R => bevy::prelude::KeyCode::SuperRight,
-        Keysym::Caps_Lock => bevy::prelude::KeyCode::CapsLock,
-        Keysym::Num_Lock => bevy::prelude::KeyCode::NumLock,
-        Keysym::Scroll_Lock => bevy::prelude::KeyCode::ScrollLock,
-
-        // Navigation & Editing
-        Keysym::Home => bevy::prelude::KeyCode::Home,
-        Keysym::End => bevy::prelude::KeyCode::End,
-        Keysym::Page_Up => bevy::prelude::KeyCode::PageUp,
-        Keysym::Page_Down => bevy::prelude::KeyCode::PageDown,
-        Keysym::Insert => bevy::prelude::KeyCode::Insert,
-        Keysym::Delete => bevy::prelude::KeyCode::Delete,
-        Keysym::BackSpace => bevy::prelude::KeyCode::Backspace, // Note: Bevy uses Backspace
-
-        // Whitespace & Control (Keysym::Return for main Enter)
-        Keysym::space => bevy::prelude::KeyCode::Space,
-        Keysym::Tab => bevy::prelude::KeyCode::Tab,
-        Keysym::Return | Keysym::KP_Enter => bevy::prelude::KeyCode::Enter, // Map both Enter and Numpad Enter
-        Keysym::Escape => bevy::prelude::KeyCode::Escape,
-
-        // Numpad Keys (might start with KP_ in some libs)
-        Keysym::KP_0 => bevy::prelude::KeyCode::Numpad0,
-        Keysym::KP_1 => bevy::prelude::KeyCode::Numpad1,
-        Keysym::KP_2 => bevy::prelude::KeyCode::Numpad2,
-        Keysym::KP_3 => bevy::prelude::KeyCode::Numpad3,
-        Keysym::KP_4 => bevy::prelude::KeyCode::Numpad4,
-        Keysym::KP_5 => bevy::prelude::KeyCode::Numpad5,
-        Keysym::KP_6 => bevy::prelude::KeyCode::Numpad6,
-        Keysym::KP_7 => bevy::prelude::KeyCode::Numpad7,
-        Keysym::KP_8 => bevy::prelude::KeyCode::Numpad8,
-        Keysym::KP_9 => bevy::prelude::KeyCode::Numpad9,
-        Keysym::KP_Add => bevy::prelude::KeyCode::NumpadAdd,
-        Keysym::KP_Subtract => bevy::prelude::KeyCode::NumpadSubtract,
-        Keysym::KP_Multiply => bevy::prelude::KeyCode::NumpadMultiply,
-        Keysym::KP_Divide => bevy::prelude::KeyCode::NumpadDivide,
-        Keysym::KP_Decimal => bevy::prelude::KeyCode::NumpadDecimal,
-        Keysym::KP_Separator => bevy::prelude::KeyCode::NumpadComma, // Bevy uses NumpadComma for the separator
-        Keysym::KP_Equal => bevy::prelude::KeyCode::NumpadEqual,
-        // KP_Enter handled above with Return
-
-        // Punctuation & Symbols (Names can vary significantly)
-        Keysym::grave => bevy::prelude::KeyCode::Backquote, // `
-        Keysym::minus => bevy::prelude::KeyCode::Minus,     // -
-        Keysym::equal => bevy::prelude::KeyCode::Equal,     // =
-        Keysym::bracketleft => bevy::prelude::KeyCode::BracketLeft, // [
-        Keysym::bracketright => bevy::prelude::KeyCode::BracketRight, // ]
-        Keysym::backslash => bevy::prelude::KeyCode::Backslash, // \
-        Keysym::semicolon => bevy::prelude::KeyCode::Semicolon, // ;
-        Keysym::apostrophe => bevy::prelude::KeyCode::Quote, // ' (Bevy uses Quote)
-        Keysym::comma => bevy::prelude::KeyCode::Comma,     // ,
-        Keysym::period => bevy::prelude::KeyCode::Period,   // .
-        Keysym::slash => bevy::prelude::KeyCode::Slash,     // /
-
-        // Other Keys
-        Keysym::Print => bevy::prelude::KeyCode::PrintScreen,
-        Keysym::Pause => bevy::prelude::KeyCode::Pause,
-        Keysym::Menu => bevy::prelude::KeyCode::ContextMenu, // Bevy uses ContextMenu
-
-        // --- Fallback ---
-        // This arm catches any Keysym not explicitly handled above.
-        // Using todo!() will cause a panic, alerting you during development
-        // that a key mapping is missing. For production, consider returning Option<KeyCode>
-        // or logging an error instead of panicking.
-        _ => {
-            // e.g., eprintln!("Warning: Unhandled keysym: {:?}", keysym);
-            // If the function returned Option<KeyCode>, you'd return None here.
-            // Since it must return KeyCode, panic is the explicit way to fail.
-            todo!("Unhandled keysym: {:?}", keysym)
-            // Or potentially return a "default" like Escape, but this hides errors:
-            // KeyCode::Escape
+        let entity = events.entity(&surface);
+        // Keys held while focus enters are reported as pressed, so they are released on leave.
+        self.focuses.insert(
+            keyboard.clone(),
+            KeyboardFocus {
+                surface,
+                pressed_keys: pressed_keys.clone(),
+            },
+        );
+        let Some(entity) = entity else {
+            return;
+        };
+        if !self.focused_by_other(events, &keyboard, entity) {
+            events.set_window_focused(entity, true);
+        }
+        for (raw_code, keysym) in pressed_keys {
+            events.send_key(synthesized_keyboard_event(
+                raw_code,
+                keysym,
+                entity,
+                ButtonState::Pressed,
+            ));
         }
     }
+
+    /// Ends the focus of `keyboard`, releasing all keys it holds.
+    pub(crate) fn leave(&mut self, events: &mut KeyboardEvents<S>, keyboard: &K) {
+        let Some(focus) = self.focuses.remove(keyboard) else {
+            return;
+        };
+        let entity = events.entity(&focus.surface);
+
+        // Release all held keys, as their release events go to the newly focused client.
+        if let Some(entity) = entity {
+            for (raw_code, keysym) in focus.pressed_keys {
+                events.send_key(synthesized_keyboard_event(
+                    raw_code,
+                    keysym,
+                    entity,
+                    ButtonState::Released,
+                ));
+            }
+        }
+        if self.focuses.is_empty() {
+            *events.world.resource_mut::<KeyboardModifiers>() = KeyboardModifiers::default();
+            events.window_events.send(KeyboardFocusLost);
+        }
+        if let Some(entity) = entity
+            && !self.focused_by_other(events, keyboard, entity)
+        {
+            events.set_window_focused(entity, false);
+        }
+    }
+
+    /// Sends a key press or release to the window focused by `keyboard`.
+    ///
+    /// Returns `false` if `keyboard` has no focus.
+    pub(crate) fn key(
+        &mut self,
+        events: &mut KeyboardEvents<S>,
+        keyboard: &K,
+        event: KeyEvent,
+        state: ButtonState,
+    ) -> bool {
+        let Some(focus) = self.focuses.get_mut(keyboard) else {
+            return false;
+        };
+        let held = focus
+            .pressed_keys
+            .iter()
+            .position(|(raw_code, _)| *raw_code == event.raw_code);
+        match (state, held) {
+            (ButtonState::Pressed, None) => focus.pressed_keys.push((event.raw_code, event.keysym)),
+            (ButtonState::Pressed, Some(_)) => {}
+            (ButtonState::Released, Some(index)) => {
+                focus.pressed_keys.remove(index);
+            }
+            // Keys that are not held were already released when the focus left.
+            (ButtonState::Released, None) => return true,
+        }
+
+        // The window may have been despawned while focused.
+        let Some(entity) = events.entity(&focus.surface) else {
+            return true;
+        };
+        events.send_key(convert_keyboard_event(event, entity, state, false));
+        true
+    }
+
+    /// Sends a repeated press of a held key to the window focused by `keyboard`.
+    pub(crate) fn repeat(&self, events: &mut KeyboardEvents<S>, keyboard: &K, event: KeyEvent) {
+        let Some(entity) = self
+            .focuses
+            .get(keyboard)
+            .and_then(|focus| events.entity(&focus.surface))
+        else {
+            return;
+        };
+        events.send_key(convert_keyboard_event(
+            event,
+            entity,
+            ButtonState::Pressed,
+            true,
+        ));
+    }
+
+    /// Returns `true` if a keyboard other than `keyboard` focuses `entity`.
+    fn focused_by_other(&self, events: &KeyboardEvents<S>, keyboard: &K, entity: Entity) -> bool {
+        self.focuses
+            .iter()
+            .any(|(id, focus)| id != keyboard && events.entity(&focus.surface) == Some(entity))
+    }
+}
+
+impl SmithayRunnerState {
+    /// Returns the keyboard focus along with the events sink of `keyboard`.
+    fn keyboard_events(
+        &mut self,
+        keyboard: &WlKeyboard,
+    ) -> (
+        &mut KeyboardFocuses<ObjectId, WlSurface>,
+        KeyboardEvents<'_, WlSurface>,
+    ) {
+        let seat = keyboard
+            .data::<KeyboardData<Self>>()
+            .and_then(|data| self.seat_devices(data.seat()))
+            .map(|devices| devices.entity);
+        let events = KeyboardEvents {
+            world: self.app.world_mut(),
+            window_events: &mut self.bevy_window_events,
+            seat,
+            resolve: |world, surface| {
+                world
+                    .non_send_resource::<SmithayWindows>()
+                    .surface_entity(surface)
+            },
+        };
+        (&mut self.keyboard_focus, events)
+    }
+
+    fn set_keyboard_serial(&mut self, keyboard: &WlKeyboard, serial: u32) {
+        let seat = keyboard
+            .data::<KeyboardData<Self>>()
+            .map(|data| data.seat().clone());
+        self.set_latest_input_serial(seat.as_ref(), serial);
+    }
+
+    /// Ends the keyboard focus of `keyboard`, releasing all keys it holds.
+    pub(crate) fn end_keyboard_focus(&mut self, keyboard: &WlKeyboard) {
+        let (focuses, mut events) = self.keyboard_events(keyboard);
+        focuses.leave(&mut events, &keyboard.id());
+    }
+
+    /// Called by the key repeat timer while a key is held down.
+    ///
+    /// The timer follows the compositor's `repeat_info` and is cancelled when the key is released
+    /// or the keyboard focus leaves the surface.
+    pub(crate) fn repeat_key(&mut self, keyboard: &WlKeyboard, event: KeyEvent) {
+        let (focuses, mut events) = self.keyboard_events(keyboard);
+        focuses.repeat(&mut events, &keyboard.id(), event);
+    }
 }
 
 impl KeyboardHandler for SmithayRunnerState {
@@ -359,14 +762,16 @@ impl KeyboardHandler for SmithayRunnerState {
         &mut self,
         _: &smithay_client_toolkit::reexports::client::Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: &smithay_client_toolkit::reexports::client::protocol::wl_keyboard::WlKeyboard,
-        wl_surface: &smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface,
-        _: u32,
-        _: &[u32],
-        _: &[smithay_client_toolkit::seat::keyboard::Keysym],
+        keyboard: &WlKeyboard,
+        wl_surface: &WlSurface,
+        serial: u32,
+        raw: &[u32],
+        keysyms: &[smithay_client_toolkit::seat::keyboard::Keysym],
     ) {
-        // Hold the active keyboard surface when focus enters.
-        self.active_keyboard_surface = Some(wl_surface.clone());
+        self.set_keyboard_serial(keyboard, serial);
+        let pressed_keys = raw.iter().copied().zip(keysyms.iter().copied()).collect();
+        let (focuses, mut events) = self.keyboard_events(keyboard);
+        focuses.enter(&mut events, keyboard.id(), wl_surface.clone(), pressed_keys);
     }
 
     /// Called when the keyboard focus leaves a surface.
@@ -374,16 +779,11 @@ impl KeyboardHandler for SmithayRunnerState {
         &mut self,
         _: &smithay_client_toolkit::reexports::client::Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        wl_surface: &smithay_client_toolkit::reexports::client::protocol::wl_keyboard::WlKeyboard,
-        _: &smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface,
+        keyboard: &WlKeyboard,
+        _: &WlSurface,
         _: u32,
     ) {
-        // Release the active keyboard surface when focus leaves.
-        if let Some(active_surface) = &mut self.active_keyboard_surface {
-            if active_surface.id() == wl_surface.id() {
-                self.active_keyboard_surface.take();
-            }
-        }
+        self.end_keyboard_focus(keyboard);
     }
 
     /// Called when a key is pressed.
@@ -391,23 +791,13 @@ impl KeyboardHandler for SmithayRunnerState {
         &mut self,
         _: &smithay_client_toolkit::reexports::client::Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: &smithay_client_toolkit::reexports::client::protocol::wl_keyboard::WlKeyboard,
-        _: u32,
+        keyboard: &WlKeyboard,
+        serial: u32,
         event: smithay_client_toolkit::seat::keyboard::KeyEvent,
     ) {
-        if let Some(active_surface) = &self.active_keyboard_surface {
-            let smithay_windows = self.world().non_send_resource::<SmithayWindows>();
-            let entity = smithay_windows.smithay_to_entity.get(&active_surface.id());
-
-            // TODO: Destroy surface when window is despawned.
-            if entity.is_none() {
-                return;
-            }
-            let entity = *entity.unwrap();
-
-            let bevy_event = convert_keyboard_event(event, entity, ButtonState::Pressed);
-            self.bevy_window_events.send(bevy_event);
-        } else {
+        self.set_keyboard_serial(keyboard, serial);
+        let (focuses, mut events) = self.keyboard_events(keyboard);
+        if !focuses.key(&mut events, &keyboard.id(), event, ButtonState::Pressed) {
             warn!("there is no active window to send keyboard events!");
         }
     }
@@ -417,25 +807,13 @@ impl KeyboardHandler for SmithayRunnerState {
         &mut self,
         _: &smithay_client_toolkit::reexports::client::Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: &smithay_client_toolkit::reexports::client::protocol::wl_keyboard::WlKeyboard,
-        _: u32,
+        keyboard: &WlKeyboard,
+        serial: u32,
         event: smithay_client_toolkit::seat::keyboard::KeyEvent,
     ) {
-        if let Some(active_surface) = &self.active_keyboard_surface {
-            let smithay_windows = self.world().non_send_resource::<SmithayWindows>();
-            let entity = smithay_windows.smithay_to_entity.get(&active_surface.id());
-
-            // TODO: Destroy surface when window is despawned.
-            if entity.is_none() {
-                return;
-            }
-            let entity = *entity.unwrap();
-
-            let bevy_event = convert_keyboard_event(event, entity, ButtonState::Released);
-            self.bevy_window_events.send(bevy_event);
-        } else {
-            panic!("There is no window available to send keyboard events!");
-        }
+        self.set_keyboard_serial(keyboard, serial);
+        let (focuses, mut events) = self.keyboard_events(keyboard);
+        focuses.key(&mut events, &keyboard.id(), event, ButtonState::Released);
     }
 
     /// Called when the keyboard modifiers are updated.
@@ -445,10 +823,218 @@ impl KeyboardHandler for SmithayRunnerState {
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
         _: &smithay_client_toolkit::reexports::client::protocol::wl_keyboard::WlKeyboard,
         _: u32,
-        _: smithay_client_toolkit::seat::keyboard::Modifiers,
+        modifiers: smithay_client_toolkit::seat::keyboard::Modifiers,
         _: u32,
     ) {
+        *self.world_mut().resource_mut::<KeyboardModifiers>() = modifiers.into();
     }
 }
 
 delegate_keyboard!(SmithayRunnerState);
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::Events;
+
+    use super::*;
+    use crate::input::seat::SeatInput;
+
+    /// Runs the keyboard focus of a single seat against a world, with window entities standing in
+    /// for their surfaces.
+    struct Harness {
+        world: World,
+        window_events: Vec<WindowEvent>,
+        focuses: KeyboardFocuses<u32, Entity>,
+        seat: Entity,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let mut world = World::new();
+            world.init_resource::<KeyboardModifiers>();
+            world.init_resource::<Events<SeatInput>>();
+            let seat = world.spawn_empty().id();
+            Self {
+                world,
+                window_events: Vec::new(),
+                focuses: KeyboardFocuses::default(),
+                seat,
+            }
+        }
+
+        fn window(&mut self) -> Entity {
+            self.world.spawn(Window::default()).id()
+        }
+
+        fn focused(&self, window: Entity) -> bool {
+            self.world.get::<Window>(window).unwrap().focused
+        }
+
+        fn run<T>(
+            &mut self,
+            f: impl FnOnce(&mut KeyboardFocuses<u32, Entity>, &mut KeyboardEvents<Entity>) -> T,
+        ) -> T {
+            let mut events = KeyboardEvents {
+                world: &mut self.world,
+                window_events: &mut self.window_events,
+                seat: Some(self.seat),
+                resolve: |world, window| world.get_entity(*window).ok().map(|_| *window),
+            };
+            f(&mut self.focuses, &mut events)
+        }
+
+        fn enter(&mut self, keyboard: u32, window: Entity, pressed_keys: &[(u32, Keysym)]) {
+            self.run(|focuses, events| {
+                focuses.enter(events, keyboard, window, pressed_keys.to_vec())
+            });
+        }
+
+        fn leave(&mut self, keyboard: u32) {
+            self.run(|focuses, events| focuses.leave(events, &keyboard));
+        }
+
+        fn key(
+            &mut self,
+            keyboard: u32,
+            raw_code: u32,
+            keysym: Keysym,
+            state: ButtonState,
+        ) -> bool {
+            let event = KeyEvent {
+                time: 0,
+                raw_code,
+                keysym,
+                utf8: None,
+            };
+            self.run(|focuses, events| focuses.key(events, &keyboard, event, state))
+        }
+
+        fn take_events(&mut self) -> Vec<WindowEvent> {
+            std::mem::take(&mut self.window_events)
+        }
+    }
+
+    fn key(window: Entity, raw_code: u32, keysym: Keysym, state: ButtonState) -> WindowEvent {
+        synthesized_keyboard_event(raw_code, keysym, window, state).into()
+    }
+
+    fn focused(window: Entity, focused: bool) -> WindowEvent {
+        WindowFocused { window, focused }.into()
+    }
+
+    #[test]
+    fn enter_without_leave_moves_focus_to_other_window() {
+        let mut harness = Harness::new();
+        let (first, second) = (harness.window(), harness.window());
+
+        harness.enter(1, first, &[]);
+        assert!(harness.key(1, 30, Keysym::a, ButtonState::Pressed));
+        assert_eq!(
+            harness.take_events(),
+            vec![
+                focused(first, true),
+                key(first, 30, Keysym::a, ButtonState::Pressed)
+            ]
+        );
+
+        harness.enter(1, second, &[]);
+        assert_eq!(
+            harness.take_events(),
+            vec![
+                key(first, 30, Keysym::a, ButtonState::Released),
+                KeyboardFocusLost.into(),
+                focused(first, false),
+                focused(second, true),
+            ]
+        );
+        assert!(!harness.focused(first));
+        assert!(harness.focused(second));
+
+        let seat_inputs = harness.world.resource::<Events<SeatInput>>();
+        assert_eq!(seat_inputs.len(), 2);
+        assert!(
+            seat_inputs
+                .iter_current_update_events()
+                .all(|input| input.seat == harness.seat)
+        );
+    }
+
+    #[test]
+    fn release_after_leave_is_dropped() {
+        let mut harness = Harness::new();
+        let (first, second) = (harness.window(), harness.window());
+
+        harness.enter(1, first, &[]);
+        harness.key(1, 30, Keysym::a, ButtonState::Pressed);
+        harness.take_events();
+        harness.leave(1);
+        assert_eq!(
+            harness.take_events(),
+            vec![
+                key(first, 30, Keysym::a, ButtonState::Released),
+                KeyboardFocusLost.into(),
+                focused(first, false),
+            ]
+        );
+        assert!(!harness.key(1, 30, Keysym::a, ButtonState::Released));
+        assert!(harness.take_events().is_empty());
+
+        // The key is still held when the focus comes back, so its release arrives there.
+        harness.enter(1, second, &[(30, Keysym::a)]);
+        assert_eq!(
+            harness.take_events(),
+            vec![
+                focused(second, true),
+                key(second, 30, Keysym::a, ButtonState::Pressed)
+            ]
+        );
+        assert!(harness.key(1, 30, Keysym::a, ButtonState::Released));
+        assert!(harness.key(1, 30, Keysym::a, ButtonState::Released));
+        assert_eq!(
+            harness.take_events(),
+            vec![key(second, 30, Keysym::a, ButtonState::Released)]
+        );
+    }
+
+    #[test]
+    fn window_stays_focused_by_other_keyboard() {
+        let mut harness = Harness::new();
+        let window = harness.window();
+
+        harness.enter(1, window, &[]);
+        harness.enter(2, window, &[]);
+        assert_eq!(harness.take_events(), vec![focused(window, true)]);
+        harness.world.resource_mut::<KeyboardModifiers>().ctrl = true;
+
+        harness.leave(1);
+        assert!(harness.take_events().is_empty());
+        assert!(harness.focused(window));
+        assert!(harness.world.resource::<KeyboardModifiers>().ctrl);
+
+        harness.leave(2);
+        assert_eq!(
+            harness.take_events(),
+            vec![KeyboardFocusLost.into(), focused(window, false)]
+        );
+        assert!(!harness.focused(window));
+        assert!(!harness.world.resource::<KeyboardModifiers>().ctrl);
+    }
+
+    #[test]
+    fn despawned_window_receives_no_keys() {
+        let mut harness = Harness::new();
+        let window = harness.window();
+
+        harness.enter(1, window, &[]);
+        harness.key(1, 30, Keysym::a, ButtonState::Pressed);
+        harness.take_events();
+        harness.world.despawn(window);
+
+        assert!(harness.key(1, 31, Keysym::s, ButtonState::Pressed));
+        assert!(harness.key(1, 30, Keysym::a, ButtonState::Released));
+        assert!(harness.take_events().is_empty());
+
+        harness.leave(1);
+        assert_eq!(harness.take_events(), vec![KeyboardFocusLost.into()]);
+    }
+}
@@ -1,3 +1,10 @@
-mod keyboard;
+pub(crate) mod cursor;
+pub(crate) mod drag_and_drop;
+pub(crate) mod gestures;
+pub(crate) mod keyboard;
 mod pointer;
-mod touch;
+pub(crate) mod pointer_constraints;
+pub(crate) mod seat;
+pub(crate) mod tablet;
+pub(crate) mod text_input;
+pub(crate) mod touch;
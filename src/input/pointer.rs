@@ -1,13 +1,22 @@
 use bevy::{
     input::{
         ButtonState,
-        mouse::{MouseButtonInput, MouseScrollUnit, MouseWheel},
+        mouse::{MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel},
     },
-    prelude::MouseButton,
+    math::{DVec2, Vec2},
+    prelude::{Entity, MouseButton},
     window::{CursorEntered, CursorLeft, CursorMoved, Window, WindowEvent},
 };
 use smithay_client_toolkit::{
-    delegate_pointer, reexports::client::Proxy, seat::pointer::PointerHandler,
+    delegate_pointer, delegate_relative_pointer,
+    reexports::{
+        client::{Connection, Proxy, QueueHandle, protocol::wl_pointer::WlPointer},
+        protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1::ZwpRelativePointerV1,
+    },
+    seat::{
+        pointer::{AxisScroll, PointerData, PointerEventKind, PointerHandler},
+        relative_pointer::{RelativeMotionEvent, RelativePointerHandler},
+    },
 };
 
 use crate::{smithay_windows::SmithayWindows, state::SmithayRunnerState};
@@ -24,15 +33,70 @@ fn convert_to_mouse_button(button: u32) -> MouseButton {
     }
 }
 
+/// Converts a scroll of a pointer frame to a Bevy scroll unit and delta, matching winit.
+///
+/// Wheels report discrete steps, which are preferred over their pixel values and scroll by lines.
+/// Continuous sources like touchpads scroll by physical pixels. Wayland uses the inverse sign of
+/// winit. Returns `None` if the frame does not scroll, e.g. when it only marks the end of a
+/// kinetic scroll with axis_stop.
+fn convert_axis_scroll(
+    horizontal: &AxisScroll,
+    vertical: &AxisScroll,
+    scale_factor: f32,
+) -> Option<(MouseScrollUnit, f32, f32)> {
+    if horizontal.discrete != 0 || vertical.discrete != 0 {
+        Some((
+            MouseScrollUnit::Line,
+            -horizontal.discrete as f32,
+            -vertical.discrete as f32,
+        ))
+    } else if horizontal.absolute != 0.0 || vertical.absolute != 0.0 {
+        Some((
+            MouseScrollUnit::Pixel,
+            -horizontal.absolute as f32 * scale_factor,
+            -vertical.absolute as f32 * scale_factor,
+        ))
+    } else {
+        None
+    }
+}
+
+impl SmithayRunnerState {
+    /// Sets the physical cursor position of the window `entity`.
+    fn set_cursor_position(&mut self, entity: Entity, position: Option<DVec2>) {
+        if let Some(mut window) = self.world_mut().get_mut::<Window>(entity) {
+            window.set_physical_cursor_position(position);
+        }
+    }
+}
+
 impl PointerHandler for SmithayRunnerState {
     fn pointer_frame(
         &mut self,
         _: &smithay_client_toolkit::reexports::client::Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: &smithay_client_toolkit::reexports::client::protocol::wl_pointer::WlPointer,
+        pointer: &smithay_client_toolkit::reexports::client::protocol::wl_pointer::WlPointer,
         events: &[smithay_client_toolkit::seat::pointer::PointerEvent],
     ) {
+        let seat = pointer
+            .data::<PointerData>()
+            .map(|data| data.seat().clone());
         for event in events {
+            if let PointerEventKind::Press { serial, .. }
+            | PointerEventKind::Release { serial, .. } = event.kind
+            {
+                self.set_latest_input_serial(seat.as_ref(), serial);
+            }
+            match event.kind {
+                PointerEventKind::Press { serial, .. } => {
+                    self.set_latest_press_serial(seat.as_ref(), serial)
+                }
+                // The implicit grab of the press ends along with it.
+                PointerEventKind::Release { .. } | PointerEventKind::Leave { .. } => {
+                    self.clear_latest_press_serial(seat.as_ref())
+                }
+                _ => {}
+            }
             let smithay_windows = self.world().non_send_resource::<SmithayWindows>();
             let window_id = event.surface.id();
             let entity = smithay_windows.smithay_to_entity.get(&window_id);
@@ -48,21 +112,40 @@ impl PointerHandler for SmithayRunnerState {
                 x: event.position.0 as f32,
                 y: event.position.1 as f32,
             };
-            let delta = window
+            let physical_delta = window
                 .physical_cursor_position()
-                .map(|old_position| (position - old_position) / window.scale_factor());
+                .map(|old_position| position - old_position);
+            let delta = physical_delta.map(|delta| delta / window.scale_factor());
+            let devices = seat.as_ref().and_then(|seat| self.seat_devices_mut(seat));
             let pointer_event: WindowEvent = match event.kind {
                 smithay_client_toolkit::seat::pointer::PointerEventKind::Enter { .. } => {
+                    // The cursor has to be set again on every enter.
+                    if let Some(devices) = devices {
+                        devices.pointer_focus = Some(entity);
+                        devices.applied_cursor = None;
+                    }
+                    // The position before the pointer left must not count as motion.
+                    self.set_cursor_position(entity, None);
                     CursorEntered { window: entity }.into()
                 }
                 smithay_client_toolkit::seat::pointer::PointerEventKind::Leave { .. } => {
+                    if let Some(devices) = devices
+                        && devices.pointer_focus == Some(entity)
+                    {
+                        devices.pointer_focus = None;
+                    }
+                    self.set_cursor_position(entity, None);
                     CursorLeft { window: entity }.into()
                 }
                 smithay_client_toolkit::seat::pointer::PointerEventKind::Motion { .. } => {
-                    self.world_mut()
-                        .get_mut::<Window>(entity)
-                        .unwrap()
-                        .set_physical_cursor_position(Some(position.as_dvec2()));
+                    // Without relative pointer support, derive the motion from cursor positions,
+                    // skipping the first motion after the pointer entered.
+                    if devices.is_none_or(|devices| devices.relative_pointer.is_none())
+                        && let Some(delta) = physical_delta
+                    {
+                        self.send_seat_input(seat.as_ref(), MouseMotion { delta });
+                    }
+                    self.set_cursor_position(entity, Some(position.as_dvec2()));
                     position /= window.scale_factor();
                     CursorMoved {
                         window: entity,
@@ -91,16 +174,40 @@ impl PointerHandler for SmithayRunnerState {
                     horizontal,
                     vertical,
                     ..
-                } => MouseWheel {
-                    unit: MouseScrollUnit::Pixel,
-                    x: horizontal.absolute as f32,
-                    y: vertical.absolute as f32,
-                    window: entity,
-                }
-                .into(),
+                } => match convert_axis_scroll(&horizontal, &vertical, window.scale_factor()) {
+                    Some((unit, x, y)) => MouseWheel {
+                        unit,
+                        x,
+                        y,
+                        window: entity,
+                    }
+                    .into(),
+                    // Frames only carrying the axis source or the end of a scroll sequence.
+                    None => continue,
+                },
             };
-            self.bevy_window_events.push(pointer_event);
+            self.send_seat_input(seat.as_ref(), pointer_event);
         }
     }
 }
+
+impl RelativePointerHandler for SmithayRunnerState {
+    fn relative_pointer_motion(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &ZwpRelativePointerV1,
+        pointer: &WlPointer,
+        event: RelativeMotionEvent,
+    ) {
+        let seat = pointer
+            .data::<PointerData>()
+            .map(|data| data.seat().clone());
+        // Unaccelerated deltas match the raw device motion reported by winit.
+        let delta = Vec2::new(event.delta_unaccel.0 as f32, event.delta_unaccel.1 as f32);
+        self.send_seat_input(seat.as_ref(), MouseMotion { delta });
+    }
+}
+
 delegate_pointer!(SmithayRunnerState);
+delegate_relative_pointer!(SmithayRunnerState);
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use bevy::{
+    log::warn,
+    math::Rect,
+    prelude::*,
+    window::{CursorGrabMode, Window},
+};
+use smithay_client_toolkit::{
+    compositor::Region,
+    delegate_pointer_constraints,
+    globals::ProvidesBoundGlobal,
+    reexports::{
+        client::{
+            Connection, Proxy, QueueHandle,
+            backend::ObjectId,
+            protocol::{wl_pointer::WlPointer, wl_surface::WlSurface},
+        },
+        protocols::wp::pointer_constraints::zv1::client::{
+            zwp_confined_pointer_v1::ZwpConfinedPointerV1,
+            zwp_locked_pointer_v1::ZwpLockedPointerV1, zwp_pointer_constraints_v1::Lifetime,
+        },
+    },
+    seat::pointer_constraints::PointerConstraintsHandler,
+};
+
+use crate::{smithay_windows::SmithayWindows, state::SmithayRunnerState};
+
+/// Limits the region of a window in which [`CursorGrabMode::Locked`] and
+/// [`CursorGrabMode::Confined`] apply.
+///
+/// The rect is given in logical pixels relative to the top left corner of the window. Without
+/// this component, the whole window is used.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CursorGrabRegion(pub Rect);
+
+/// Sent when the compositor activates the pointer lock or confinement of a window.
+#[derive(Event, Debug, Clone)]
+pub struct CursorGrabActivated {
+    pub window: Entity,
+    pub mode: CursorGrabMode,
+}
+
+/// Sent when the pointer lock or confinement of a window is deactivated, for example because the
+/// window lost focus.
+///
+/// The constraint is activated again once the window regains focus.
+#[derive(Event, Debug, Clone)]
+pub struct CursorGrabDeactivated {
+    pub window: Entity,
+    pub mode: CursorGrabMode,
+}
+
+enum Constraint {
+    Locked(ZwpLockedPointerV1),
+    Confined(ZwpConfinedPointerV1),
+}
+
+impl Drop for Constraint {
+    fn drop(&mut self) {
+        match self {
+            Constraint::Locked(locked_pointer) => locked_pointer.destroy(),
+            Constraint::Confined(confined_pointer) => confined_pointer.destroy(),
+        }
+    }
+}
+
+struct ActiveConstraint {
+    mode: CursorGrabMode,
+    region: Option<Rect>,
+    _constraint: Constraint,
+}
+
+/// The pointer constraints created for windows with a [`CursorGrabMode`], one per window and
+/// pointer.
+#[derive(Default)]
+pub(crate) struct PointerConstraints {
+    constraints: HashMap<(Entity, ObjectId), ActiveConstraint>,
+}
+
+impl SmithayRunnerState {
+    /// Creates and destroys pointer constraints to match the windows' `cursor_options.grab_mode`.
+    pub(crate) fn update_pointer_constraints(&mut self, qh: &QueueHandle<Self>) {
+        if self.pointer_constraints_state.bound_global().is_err() {
+            return;
+        }
+        // A grabbed cursor applies to the pointers of all seats.
+        let pointers = self
+            .seats
+            .iter()
+            .filter_map(|devices| devices.pointer.as_ref())
+            .map(|pointer| pointer.pointer().clone())
+            .collect::<Vec<_>>();
+
+        let world = self.app.world_mut();
+        let mut query = world.query::<(Entity, &Window, Option<&CursorGrabRegion>)>();
+        let grabs = query
+            .iter(world)
+            .filter(|(_, window, _)| window.cursor_options.grab_mode != CursorGrabMode::None)
+            .map(|(entity, window, region)| {
+                (
+                    entity,
+                    window.cursor_options.grab_mode,
+                    region.map(|region| region.0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.pointer_constraints
+            .constraints
+            .retain(|(entity, pointer_id), active| {
+                pointers.iter().any(|pointer| pointer.id() == *pointer_id)
+                    && grabs.iter().any(|(grab_entity, mode, region)| {
+                        grab_entity == entity && *mode == active.mode && *region == active.region
+                    })
+            });
+
+        let smithay_windows = world.non_send_resource::<SmithayWindows>();
+        for ((entity, mode, region), pointer) in grabs
+            .into_iter()
+            .flat_map(|grab| pointers.iter().map(move |pointer| (grab, pointer)))
+        {
+            let key = (entity, pointer.id());
+            if self.pointer_constraints.constraints.contains_key(&key) {
+                continue;
+            }
+            let Some(surface) = smithay_windows.wl_surface(entity) else {
+                continue;
+            };
+
+            let wl_region = match (region, smithay_windows.compositor.as_ref()) {
+                (Some(rect), Some(compositor)) => match Region::new(compositor) {
+                    Ok(wl_region) => {
+                        wl_region.add(
+                            rect.min.x as i32,
+                            rect.min.y as i32,
+                            rect.width() as i32,
+                            rect.height() as i32,
+                        );
+                        Some(wl_region)
+                    }
+                    Err(err) => {
+                        warn!("failed to create the cursor grab region: {}", err);
+                        None
+                    }
+                },
+                _ => None,
+            };
+            let wl_region = wl_region.as_ref().map(|wl_region| wl_region.wl_region());
+
+            let constraint = match mode {
+                CursorGrabMode::Locked => self
+                    .pointer_constraints_state
+                    .lock_pointer(surface, pointer, wl_region, Lifetime::Persistent, qh)
+                    .map(Constraint::Locked),
+                CursorGrabMode::Confined => self
+                    .pointer_constraints_state
+                    .confine_pointer(surface, pointer, wl_region, Lifetime::Persistent, qh)
+                    .map(Constraint::Confined),
+                CursorGrabMode::None => unreachable!(),
+            };
+            match constraint {
+                Ok(constraint) => {
+                    self.pointer_constraints.constraints.insert(
+                        key,
+                        ActiveConstraint {
+                            mode,
+                            region,
+                            _constraint: constraint,
+                        },
+                    );
+                }
+                Err(err) => {
+                    warn!("failed to grab the cursor for window {}: {}", entity, err);
+                }
+            }
+        }
+    }
+}
+
+impl PointerConstraintsHandler for SmithayRunnerState {
+    fn confined(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &ZwpConfinedPointerV1,
+        surface: &WlSurface,
+        _: &WlPointer,
+    ) {
+        if let Some(window) = self.surface_entity(surface) {
+            self.world_mut().send_event(CursorGrabActivated {
+                window,
+                mode: CursorGrabMode::Confined,
+            });
+        }
+    }
+
+    fn unconfined(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &ZwpConfinedPointerV1,
+        surface: &WlSurface,
+        _: &WlPointer,
+    ) {
+        if let Some(window) = self.surface_entity(surface) {
+            self.world_mut().send_event(CursorGrabDeactivated {
+                window,
+                mode: CursorGrabMode::Confined,
+            });
+        }
+    }
+
+    fn locked(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &ZwpLockedPointerV1,
+        surface: &WlSurface,
+        _: &WlPointer,
+    ) {
+        if let Some(window) = self.surface_entity(surface) {
+            self.world_mut().send_event(CursorGrabActivated {
+                window,
+                mode: CursorGrabMode::Locked,
+            });
+        }
+    }
+
+    fn unlocked(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: &ZwpLockedPointerV1,
+        surface: &WlSurface,
+        _: &WlPointer,
+    ) {
+        if let Some(window) = self.surface_entity(surface) {
+            self.world_mut().send_event(CursorGrabDeactivated {
+                window,
+                mode: CursorGrabMode::Locked,
+            });
+        }
+    }
+}
+
+delegate_pointer_constraints!(SmithayRunnerState);
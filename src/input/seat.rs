@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::entity::EntityHashMap, math::Vec2, prelude::*, window::WindowEvent};
+use smithay_client_toolkit::{
+    reexports::{
+        client::protocol::{wl_seat::WlSeat, wl_touch::WlTouch},
+        protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1::ZwpRelativePointerV1,
+    },
+    seat::{Capability, pointer::ThemedPointer},
+};
+
+use crate::{
+    AppSendEvent,
+    input::{cursor::AppliedCursor, touch::ActiveContact},
+    state::SmithayRunnerState,
+};
+
+/// A `wl_seat`, the group of input devices used by a single user.
+///
+/// The runner spawns an entity with this component for every seat announced by the compositor
+/// and despawns it when the seat is removed. [`SeatInput`] refers to these entities.
+#[derive(Component, Debug, Clone, Default)]
+pub struct Seat {
+    /// The name of the seat, e.g. `seat0`, once the compositor announced it.
+    pub name: Option<String>,
+}
+
+/// Sent along with every input event, naming the [`Seat`] that produced it.
+///
+/// Keyboard, pointer, touch and gesture events are sent as usual; this event allows telling
+/// apart the devices of several seats.
+#[derive(Event, Debug, Clone)]
+pub struct SeatInput {
+    pub seat: Entity,
+    pub event: WindowEvent,
+}
+
+/// A kind of input device a [`Seat`] can provide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeatCapability {
+    Keyboard,
+    Pointer,
+    Touch,
+}
+
+/// Fails for capabilities unknown to this crate, which sctk may add in newer versions.
+impl TryFrom<Capability> for SeatCapability {
+    type Error = Capability;
+
+    fn try_from(capability: Capability) -> Result<Self, Self::Error> {
+        match capability {
+            Capability::Keyboard => Ok(SeatCapability::Keyboard),
+            Capability::Pointer => Ok(SeatCapability::Pointer),
+            Capability::Touch => Ok(SeatCapability::Touch),
+            capability => Err(capability),
+        }
+    }
+}
+
+impl From<SeatCapability> for Capability {
+    fn from(capability: SeatCapability) -> Self {
+        match capability {
+            SeatCapability::Keyboard => Capability::Keyboard,
+            SeatCapability::Pointer => Capability::Pointer,
+            SeatCapability::Touch => Capability::Touch,
+        }
+    }
+}
+
+/// Sent when a seat gains a keyboard, pointer or touch device.
+#[derive(Event, Debug, Clone)]
+pub struct InputDeviceAdded {
+    pub seat: Entity,
+    pub capability: SeatCapability,
+}
+
+/// Sent when a seat loses a keyboard, pointer or touch device, including when the seat itself is
+/// removed.
+#[derive(Event, Debug, Clone)]
+pub struct InputDeviceRemoved {
+    pub seat: Entity,
+    pub capability: SeatCapability,
+}
+
+/// The devices provided by a single seat.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SeatCapabilities {
+    pub keyboard: bool,
+    pub pointer: bool,
+    pub touch: bool,
+}
+
+impl SeatCapabilities {
+    pub fn has(&self, capability: SeatCapability) -> bool {
+        match capability {
+            SeatCapability::Keyboard => self.keyboard,
+            SeatCapability::Pointer => self.pointer,
+            SeatCapability::Touch => self.touch,
+        }
+    }
+
+    fn set(&mut self, capability: SeatCapability, available: bool) {
+        match capability {
+            SeatCapability::Keyboard => self.keyboard = available,
+            SeatCapability::Pointer => self.pointer = available,
+            SeatCapability::Touch => self.touch = available,
+        }
+    }
+}
+
+/// The input devices currently available on each [`Seat`].
+///
+/// Updated along with [`InputDeviceAdded`] and [`InputDeviceRemoved`], e.g. to switch between
+/// touch and pointer layouts.
+#[derive(Resource, Debug, Default, Clone)]
+pub struct InputCapabilities {
+    seats: EntityHashMap<SeatCapabilities>,
+}
+
+impl InputCapabilities {
+    /// Returns the devices of `seat`, or `None` if there is no such seat.
+    pub fn seat(&self, seat: Entity) -> Option<SeatCapabilities> {
+        self.seats.get(&seat).copied()
+    }
+
+    /// Iterates over all seats and their devices.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, SeatCapabilities)> + '_ {
+        self.seats
+            .iter()
+            .map(|(seat, capabilities)| (*seat, *capabilities))
+    }
+
+    /// Returns `true` if any seat provides `capability`.
+    pub fn any(&self, capability: SeatCapability) -> bool {
+        self.seats
+            .values()
+            .any(|capabilities| capabilities.has(capability))
+    }
+
+    pub fn has_keyboard(&self) -> bool {
+        self.any(SeatCapability::Keyboard)
+    }
+
+    pub fn has_pointer(&self) -> bool {
+        self.any(SeatCapability::Pointer)
+    }
+
+    pub fn has_touch(&self) -> bool {
+        self.any(SeatCapability::Touch)
+    }
+}
+
+/// The devices, focus and cursor of a single seat.
+pub(crate) struct SeatDevices {
+    pub(crate) seat: WlSeat,
+    pub(crate) entity: Entity,
+    /// Distinguishes the touch points of different seats, which share their ids.
+    touch_offset: u64,
+
+    pub(crate) pointer: Option<ThemedPointer>,
+    pub(crate) relative_pointer: Option<ZwpRelativePointerV1>,
+    pub(crate) touch: Option<WlTouch>,
+
+    pub(crate) pointer_focus: Option<Entity>,
+    /// The serial of the latest pointer button press or touch down while it is still held, which
+    /// can start a drag.
+    pub(crate) latest_press_serial: Option<u32>,
+    pub(crate) applied_cursor: Option<AppliedCursor>,
+    pub(crate) active_touches: HashMap<i32, (Entity, Vec2)>,
+    pub(crate) touch_contacts: HashMap<i32, ActiveContact>,
+}
+
+impl SeatDevices {
+    /// Returns the bevy id of the touch point `id` of this seat.
+    ///
+    /// The touch points of the first seat keep their wayland ids.
+    pub(crate) fn touch_id(&self, id: i32) -> u64 {
+        self.touch_offset | id as u32 as u64
+    }
+}
+
+/// Queues an input event of the seat entity `seat` and sends its [`SeatInput`].
+pub(crate) fn send_seat_event(
+    world: &mut World,
+    window_events: &mut Vec<WindowEvent>,
+    seat: Option<Entity>,
+    event: impl Into<WindowEvent>,
+) {
+    let event = event.into();
+    window_events.send(event.clone());
+    if let Some(seat) = seat {
+        world.send_event(SeatInput { seat, event });
+    }
+}
+
+impl SmithayRunnerState {
+    /// Spawns the entity of a seat, unless it already has one.
+    pub(crate) fn add_seat(&mut self, seat: &WlSeat) {
+        if self.seat_devices(seat).is_some() {
+            return;
+        }
+        let entity = self.world_mut().spawn(Seat::default()).id();
+        self.world_mut()
+            .resource_mut::<InputCapabilities>()
+            .seats
+            .insert(entity, SeatCapabilities::default());
+        let touch_offset = (self.seats_added as u64) << 32;
+        self.seats_added += 1;
+        self.seats.push(SeatDevices {
+            seat: seat.clone(),
+            entity,
+            touch_offset,
+            pointer: None,
+            relative_pointer: None,
+            touch: None,
+            pointer_focus: None,
+            latest_press_serial: None,
+            applied_cursor: None,
+            active_touches: HashMap::new(),
+            touch_contacts: HashMap::new(),
+        });
+    }
+
+    /// Despawns the entity of a removed seat, whose devices were already released.
+    pub(crate) fn remove_seat_devices(&mut self, seat: &WlSeat) {
+        let Some(index) = self.seats.iter().position(|devices| &devices.seat == seat) else {
+            return;
+        };
+        let devices = self.seats.remove(index);
+        let world = self.world_mut();
+        world
+            .resource_mut::<InputCapabilities>()
+            .seats
+            .remove(&devices.entity);
+        world.despawn(devices.entity);
+    }
+
+    /// Records that `seat` gained or lost a device, sending [`InputDeviceAdded`] or
+    /// [`InputDeviceRemoved`].
+    pub(crate) fn set_seat_capability(
+        &mut self,
+        seat: &WlSeat,
+        capability: SeatCapability,
+        available: bool,
+    ) {
+        let Some(entity) = self.seat_devices(seat).map(|devices| devices.entity) else {
+            return;
+        };
+        let world = self.world_mut();
+        let mut capabilities = world.resource_mut::<InputCapabilities>();
+        let Some(seat_capabilities) = capabilities.seats.get_mut(&entity) else {
+            return;
+        };
+        if seat_capabilities.has(capability) == available {
+            return;
+        }
+        seat_capabilities.set(capability, available);
+        if available {
+            world.send_event(InputDeviceAdded {
+                seat: entity,
+                capability,
+            });
+        } else {
+            world.send_event(InputDeviceRemoved {
+                seat: entity,
+                capability,
+            });
+        }
+    }
+
+    /// Returns the devices currently recorded for `seat`.
+    pub(crate) fn seat_capabilities(&self, seat: &WlSeat) -> SeatCapabilities {
+        self.seat_devices(seat)
+            .and_then(|devices| {
+                self.world()
+                    .resource::<InputCapabilities>()
+                    .seat(devices.entity)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Copies the seat names announced by the compositor to the seat entities.
+    pub(crate) fn update_seat_names(&mut self) {
+        for index in 0..self.seats.len() {
+            let devices = &self.seats[index];
+            let name = self
+                .seat_state
+                .info(&devices.seat)
+                .and_then(|info| info.name);
+            let entity = devices.entity;
+            if let Some(mut seat) = self.world_mut().get_mut::<Seat>(entity)
+                && seat.name != name
+            {
+                seat.name = name;
+            }
+        }
+    }
+
+    /// Remembers the serial of a pointer button press or touch down on `seat`.
+    pub(crate) fn set_latest_press_serial(&mut self, seat: Option<&WlSeat>, serial: u32) {
+        if let Some(devices) = seat.and_then(|seat| self.seat_devices_mut(seat)) {
+            devices.latest_press_serial = Some(serial);
+        }
+    }
+
+    /// Forgets the press serial of `seat` once the press that started the grab has ended.
+    pub(crate) fn clear_latest_press_serial(&mut self, seat: Option<&WlSeat>) {
+        if let Some(devices) = seat.and_then(|seat| self.seat_devices_mut(seat)) {
+            devices.latest_press_serial = None;
+        }
+    }
+
+    /// Returns the seat and serial of the press held on the seat of the latest input.
+    ///
+    /// Other seats are not considered, as the compositor only accepts the serial of the seat
+    /// holding the implicit grab.
+    pub(crate) fn latest_press_serial(&self) -> Option<(WlSeat, u32)> {
+        let (seat, _) = self.latest_input_serial.as_ref()?;
+        let serial = self.seat_devices(seat)?.latest_press_serial?;
+        Some((seat.clone(), serial))
+    }
+
+    pub(crate) fn seat_devices(&self, seat: &WlSeat) -> Option<&SeatDevices> {
+        self.seats.iter().find(|devices| &devices.seat == seat)
+    }
+
+    pub(crate) fn seat_devices_mut(&mut self, seat: &WlSeat) -> Option<&mut SeatDevices> {
+        self.seats.iter_mut().find(|devices| &devices.seat == seat)
+    }
+
+    /// Sends an input event produced by `seat`, followed by its [`SeatInput`].
+    pub(crate) fn send_seat_input(&mut self, seat: Option<&WlSeat>, event: impl Into<WindowEvent>) {
+        let entity = seat
+            .and_then(|seat| self.seat_devices(seat))
+            .map(|devices| devices.entity);
+        send_seat_event(
+            self.app.world_mut(),
+            &mut self.bevy_window_events,
+            entity,
+            event,
+        );
+    }
+}
@@ -0,0 +1,743 @@
+use bevy::{
+    input::{
+        ButtonState,
+        touch::{ForceTouch, TouchInput, TouchPhase},
+    },
+    math::Vec2,
+    prelude::*,
+};
+use smithay_client_toolkit::{
+    globals::GlobalData,
+    reexports::{
+        client::{
+            Connection, Dispatch, Proxy, QueueHandle, WEnum, event_created_child,
+            globals::GlobalList, protocol::wl_seat::WlSeat,
+        },
+        protocols::wp::tablet::zv2::client::{
+            zwp_tablet_manager_v2::ZwpTabletManagerV2,
+            zwp_tablet_pad_dial_v2::{self, ZwpTabletPadDialV2},
+            zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+            zwp_tablet_pad_ring_v2::{self, ZwpTabletPadRingV2},
+            zwp_tablet_pad_strip_v2::{self, ZwpTabletPadStripV2},
+            zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+            zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
+            zwp_tablet_tool_v2::{self, ZwpTabletToolV2},
+            zwp_tablet_v2::{self, ZwpTabletV2},
+        },
+    },
+};
+
+use crate::state::SmithayRunnerState;
+
+/// Tablet tools share the id space of touch points, this bit keeps their ids apart.
+const TABLET_TOOL_ID: u64 = 1 << 63;
+
+/// The largest value of the normalized pressure and distance axes.
+const NORMALIZED_MAX: f32 = 65535.0;
+
+/// The physical type of a tablet tool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TabletToolType {
+    Pen,
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+    Finger,
+    Mouse,
+    Lens,
+}
+
+/// A tablet tool, e.g. a pen or its eraser end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TabletTool {
+    /// Identifies the tool, matching the id of the [`TouchInput`] events it produces.
+    pub id: u64,
+    pub tool_type: TabletToolType,
+    /// The serial number of the tool, if the hardware reports one.
+    ///
+    /// It stays the same when the tool is used on another tablet.
+    pub hardware_serial: Option<u64>,
+}
+
+/// A change of a tablet tool's state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TabletToolEvent {
+    /// The tool came into proximity of the tablet above the window.
+    ProximityIn,
+    /// The tool left the proximity of the tablet or the window.
+    ProximityOut,
+    /// The tip of the tool touched the tablet.
+    Down,
+    /// The tip of the tool stopped touching the tablet.
+    Up,
+    /// The tool moved to a position in logical pixels relative to the window.
+    Motion(Vec2),
+    /// The pressure of the tip, from 0.0 to 1.0.
+    Pressure(f32),
+    /// The distance of the tool from the tablet, from 0.0 to 1.0.
+    Distance(f32),
+    /// The tilt of the tool in degrees, positive towards the right and the bottom of the tablet.
+    Tilt(Vec2),
+    /// The clockwise rotation of the tool around its own axis, in degrees.
+    Rotation(f32),
+    /// The position of the tool's slider, from -1.0 to 1.0.
+    Slider(f32),
+    /// The tool's wheel was rotated, in degrees and discrete steps.
+    Wheel { degrees: f32, clicks: i32 },
+    /// A button of the tool changed its state.
+    ///
+    /// The button is a linux input event code, e.g. `BTN_STYLUS` (0x14b).
+    Button { button: u32, state: ButtonState },
+}
+
+/// Sent for every change of a tablet tool above a window.
+///
+/// The changes of a single hardware event arrive together. While the tip touches the tablet,
+/// the tool also produces [`TouchInput`] events with [`ForceTouch::Normalized`] pressure.
+#[derive(Event, Debug, Clone)]
+pub struct TabletToolInput {
+    pub seat: Entity,
+    pub window: Entity,
+    pub tool: TabletTool,
+    pub event: TabletToolEvent,
+}
+
+/// The buttons of a tablet pad, spawned by the runner for every pad of a graphics tablet.
+#[derive(Component, Debug, Clone)]
+pub struct TabletPad {
+    pub seat: Entity,
+    /// The number of buttons of the pad.
+    pub buttons: u32,
+    pressed: Vec<u32>,
+}
+
+impl TabletPad {
+    /// Returns `true` if the button at `index` is held down.
+    pub fn pressed(&self, index: u32) -> bool {
+        self.pressed.contains(&index)
+    }
+}
+
+/// Sent when a button of a [`TabletPad`] is pressed or released.
+#[derive(Event, Debug, Clone)]
+pub struct TabletPadButtonInput {
+    pub pad: Entity,
+    /// The window the pad is focused on, if any.
+    pub window: Option<Entity>,
+    pub button: u32,
+    pub state: ButtonState,
+}
+
+/// A tool known to the compositor and the changes received since its last frame.
+struct Tool {
+    tool: ZwpTabletToolV2,
+    seat: WlSeat,
+    info: TabletTool,
+    focus: Option<Entity>,
+    position: Vec2,
+    pressure: Option<f32>,
+    down: bool,
+    /// Set when the tool left proximity, the focus is cleared once the frame is sent.
+    leaving: bool,
+    pending: Vec<TabletToolEvent>,
+    touch_phases: Vec<TouchPhase>,
+}
+
+/// A ring, strip or dial of a pad, which are only destroyed along with it.
+enum PadControl {
+    Ring(ZwpTabletPadRingV2),
+    Strip(ZwpTabletPadStripV2),
+    Dial(ZwpTabletPadDialV2),
+}
+
+impl Drop for PadControl {
+    fn drop(&mut self) {
+        match self {
+            PadControl::Ring(ring) => ring.destroy(),
+            PadControl::Strip(strip) => strip.destroy(),
+            PadControl::Dial(dial) => dial.destroy(),
+        }
+    }
+}
+
+struct Pad {
+    pad: ZwpTabletPadV2,
+    seat: WlSeat,
+    entity: Option<Entity>,
+    buttons: u32,
+    focus: Option<Entity>,
+    groups: Vec<ZwpTabletPadGroupV2>,
+    controls: Vec<PadControl>,
+}
+
+impl Drop for Pad {
+    fn drop(&mut self) {
+        self.controls.clear();
+        for group in &self.groups {
+            group.destroy();
+        }
+        self.pad.destroy();
+    }
+}
+
+pub(crate) struct TabletState {
+    manager: Option<ZwpTabletManagerV2>,
+    seats: Vec<(WlSeat, ZwpTabletSeatV2)>,
+    tools: Vec<Tool>,
+    pads: Vec<Pad>,
+    tools_added: u64,
+}
+
+impl TabletState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            manager: globals.bind(qh, 1..=2, GlobalData).ok(),
+            seats: Vec::new(),
+            tools: Vec::new(),
+            pads: Vec::new(),
+            tools_added: 0,
+        }
+    }
+
+    pub(crate) fn add_seat(&mut self, seat: &WlSeat, qh: &QueueHandle<SmithayRunnerState>) {
+        let Some(manager) = &self.manager else {
+            return;
+        };
+        let tablet_seat = manager.get_tablet_seat(seat, qh, ());
+        self.seats.push((seat.clone(), tablet_seat));
+    }
+
+    fn seat(&self, tablet_seat: &ZwpTabletSeatV2) -> Option<WlSeat> {
+        self.seats
+            .iter()
+            .find(|(_, other)| other == tablet_seat)
+            .map(|(seat, _)| seat.clone())
+    }
+
+    fn tool_mut(&mut self, tool: &ZwpTabletToolV2) -> Option<&mut Tool> {
+        self.tools.iter_mut().find(|other| &other.tool == tool)
+    }
+
+    fn pad_mut(&mut self, pad: &ZwpTabletPadV2) -> Option<&mut Pad> {
+        self.pads.iter_mut().find(|other| &other.pad == pad)
+    }
+}
+
+impl SmithayRunnerState {
+    /// Releases the tablet seat of a removed seat, along with its tools and pads.
+    pub(crate) fn remove_tablet_seat(&mut self, seat: &WlSeat) {
+        self.tablet.seats.retain(|(other, tablet_seat)| {
+            if other == seat {
+                tablet_seat.destroy();
+            }
+            other != seat
+        });
+        let (removed, tools) = std::mem::take(&mut self.tablet.tools)
+            .into_iter()
+            .partition::<Vec<_>, _>(|tool| &tool.seat == seat);
+        self.tablet.tools = tools;
+        for tool in removed {
+            self.remove_tool(tool);
+        }
+        let (removed, pads) = std::mem::take(&mut self.tablet.pads)
+            .into_iter()
+            .partition::<Vec<_>, _>(|pad| &pad.seat == seat);
+        self.tablet.pads = pads;
+        for pad in removed {
+            self.despawn_pad(pad);
+        }
+    }
+
+    /// Destroys a removed tool, cancelling its touch if the tip is still down.
+    fn remove_tool(&mut self, tool: Tool) {
+        tool.tool.destroy();
+        if !tool.down {
+            return;
+        }
+        let Some(window) = tool.focus else {
+            return;
+        };
+        let touch = TouchInput {
+            phase: TouchPhase::Canceled,
+            position: tool.position,
+            force: None,
+            id: tool.info.id,
+            window,
+        };
+        self.send_seat_input(Some(&tool.seat), touch);
+    }
+
+    fn despawn_pad(&mut self, pad: Pad) {
+        if let Some(entity) = pad.entity {
+            self.world_mut().despawn(entity);
+        }
+    }
+
+    fn seat_entity(&self, seat: &WlSeat) -> Option<Entity> {
+        self.seat_devices(seat).map(|devices| devices.entity)
+    }
+
+    /// Sends the changes of a tool received since its last frame.
+    fn send_tool_frame(&mut self, tool: &ZwpTabletToolV2) {
+        let Some(state) = self.tablet.tool_mut(tool) else {
+            return;
+        };
+        let pending = std::mem::take(&mut state.pending);
+        let touch_phases = std::mem::take(&mut state.touch_phases);
+        let (seat, info, focus, position, pressure) = (
+            state.seat.clone(),
+            state.info,
+            state.focus,
+            state.position,
+            state.pressure,
+        );
+        if state.leaving {
+            state.leaving = false;
+            state.focus = None;
+            state.down = false;
+        }
+
+        let (Some(window), Some(seat_entity)) = (focus, self.seat_entity(&seat)) else {
+            return;
+        };
+        for event in pending {
+            self.world_mut().send_event(TabletToolInput {
+                seat: seat_entity,
+                window,
+                tool: info,
+                event,
+            });
+        }
+        for phase in touch_phases {
+            let touch = TouchInput {
+                phase,
+                position,
+                force: pressure.map(|pressure| ForceTouch::Normalized(pressure as f64)),
+                id: info.id,
+                window,
+            };
+            self.send_seat_input(Some(&seat), touch);
+        }
+    }
+}
+
+/// Converts a tool type to its bevy representation.
+fn convert_tool_type(tool_type: zwp_tablet_tool_v2::Type) -> TabletToolType {
+    match tool_type {
+        zwp_tablet_tool_v2::Type::Eraser => TabletToolType::Eraser,
+        zwp_tablet_tool_v2::Type::Brush => TabletToolType::Brush,
+        zwp_tablet_tool_v2::Type::Pencil => TabletToolType::Pencil,
+        zwp_tablet_tool_v2::Type::Airbrush => TabletToolType::Airbrush,
+        zwp_tablet_tool_v2::Type::Finger => TabletToolType::Finger,
+        zwp_tablet_tool_v2::Type::Mouse => TabletToolType::Mouse,
+        zwp_tablet_tool_v2::Type::Lens => TabletToolType::Lens,
+        _ => TabletToolType::Pen,
+    }
+}
+
+fn convert_button_state<T>(state: WEnum<T>, pressed: T) -> ButtonState
+where
+    T: PartialEq,
+{
+    match state {
+        WEnum::Value(state) if state == pressed => ButtonState::Pressed,
+        _ => ButtonState::Released,
+    }
+}
+
+impl Dispatch<ZwpTabletManagerV2, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletManagerV2,
+        _: <ZwpTabletManagerV2 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_tablet_manager_v2 has no events")
+    }
+}
+
+impl Dispatch<ZwpTabletSeatV2, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        tablet_seat: &ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(seat) = state.tablet.seat(tablet_seat) else {
+            return;
+        };
+        match event {
+            // Tablets only describe the hardware, the tools report the input.
+            zwp_tablet_seat_v2::Event::TabletAdded { .. } => {}
+            zwp_tablet_seat_v2::Event::ToolAdded { id } => {
+                let tool_id = TABLET_TOOL_ID | state.tablet.tools_added;
+                state.tablet.tools_added += 1;
+                state.tablet.tools.push(Tool {
+                    tool: id,
+                    seat,
+                    info: TabletTool {
+                        id: tool_id,
+                        tool_type: TabletToolType::Pen,
+                        hardware_serial: None,
+                    },
+                    focus: None,
+                    position: Vec2::ZERO,
+                    pressure: None,
+                    down: false,
+                    leaving: false,
+                    pending: Vec::new(),
+                    touch_phases: Vec::new(),
+                });
+            }
+            zwp_tablet_seat_v2::Event::PadAdded { id } => {
+                state.tablet.pads.push(Pad {
+                    pad: id,
+                    seat,
+                    entity: None,
+                    buttons: 0,
+                    focus: None,
+                    groups: Vec::new(),
+                    controls: Vec::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(SmithayRunnerState, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletV2, ()> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        tablet: &ZwpTabletV2,
+        event: zwp_tablet_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_v2::Event::Removed = event {
+            tablet.destroy();
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletToolV2, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        tool: &ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_tool_v2::Event::Removed = event {
+            if let Some(index) = state
+                .tablet
+                .tools
+                .iter()
+                .position(|other| &other.tool == tool)
+            {
+                let tool = state.tablet.tools.remove(index);
+                state.remove_tool(tool);
+            } else {
+                tool.destroy();
+            }
+            return;
+        }
+        if let zwp_tablet_tool_v2::Event::Frame { .. } = event {
+            state.send_tool_frame(tool);
+            return;
+        }
+        let focus = match &event {
+            zwp_tablet_tool_v2::Event::ProximityIn { surface, .. } => state.surface_entity(surface),
+            _ => None,
+        };
+        let scale_factor = state
+            .tablet
+            .tools
+            .iter()
+            .find(|other| &other.tool == tool)
+            .and_then(|other| focus.or(other.focus))
+            .and_then(|entity| state.world().get::<Window>(entity))
+            .map(|window| window.scale_factor())
+            .unwrap_or(1.0);
+
+        let Some(tool) = state.tablet.tool_mut(tool) else {
+            return;
+        };
+        match event {
+            zwp_tablet_tool_v2::Event::Type {
+                tool_type: WEnum::Value(tool_type),
+            } => {
+                tool.info.tool_type = convert_tool_type(tool_type);
+            }
+            zwp_tablet_tool_v2::Event::HardwareSerial {
+                hardware_serial_hi,
+                hardware_serial_lo,
+            } => {
+                tool.info.hardware_serial =
+                    Some(((hardware_serial_hi as u64) << 32) | hardware_serial_lo as u64);
+            }
+            zwp_tablet_tool_v2::Event::ProximityIn { .. } => {
+                tool.focus = focus;
+                tool.leaving = false;
+                tool.pending.push(TabletToolEvent::ProximityIn);
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => {
+                // The compositor lifts the tip before the tool leaves, a tool still down was
+                // interrupted.
+                if tool.down {
+                    tool.touch_phases.push(TouchPhase::Canceled);
+                }
+                tool.leaving = true;
+                tool.pending.push(TabletToolEvent::ProximityOut);
+            }
+            zwp_tablet_tool_v2::Event::Down { serial } => {
+                tool.down = true;
+                tool.touch_phases.push(TouchPhase::Started);
+                tool.pending.push(TabletToolEvent::Down);
+                let seat = tool.seat.clone();
+                state.set_latest_input_serial(Some(&seat), serial);
+            }
+            zwp_tablet_tool_v2::Event::Up => {
+                tool.down = false;
+                tool.touch_phases.push(TouchPhase::Ended);
+                tool.pending.push(TabletToolEvent::Up);
+            }
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                tool.position = Vec2::new(x as f32, y as f32) / scale_factor;
+                if tool.down && tool.touch_phases.is_empty() {
+                    tool.touch_phases.push(TouchPhase::Moved);
+                }
+                tool.pending.push(TabletToolEvent::Motion(tool.position));
+            }
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                let pressure = pressure as f32 / NORMALIZED_MAX;
+                tool.pressure = Some(pressure);
+                if tool.down && tool.touch_phases.is_empty() {
+                    tool.touch_phases.push(TouchPhase::Moved);
+                }
+                tool.pending.push(TabletToolEvent::Pressure(pressure));
+            }
+            zwp_tablet_tool_v2::Event::Distance { distance } => {
+                tool.pending
+                    .push(TabletToolEvent::Distance(distance as f32 / NORMALIZED_MAX));
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                tool.pending.push(TabletToolEvent::Tilt(Vec2::new(
+                    tilt_x as f32,
+                    tilt_y as f32,
+                )));
+            }
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                tool.pending.push(TabletToolEvent::Rotation(degrees as f32));
+            }
+            zwp_tablet_tool_v2::Event::Slider { position } => {
+                tool.pending
+                    .push(TabletToolEvent::Slider(position as f32 / NORMALIZED_MAX));
+            }
+            zwp_tablet_tool_v2::Event::Wheel { degrees, clicks } => {
+                tool.pending.push(TabletToolEvent::Wheel {
+                    degrees: degrees as f32,
+                    clicks,
+                });
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                serial,
+                button,
+                state: button_state,
+            } => {
+                tool.pending.push(TabletToolEvent::Button {
+                    button,
+                    state: convert_button_state(
+                        button_state,
+                        zwp_tablet_tool_v2::ButtonState::Pressed,
+                    ),
+                });
+                let seat = tool.seat.clone();
+                state.set_latest_input_serial(Some(&seat), serial);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwpTabletPadV2, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        pad: &ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_tablet_pad_v2::Event::Group { pad_group } => {
+                if let Some(pad) = state.tablet.pad_mut(pad) {
+                    pad.groups.push(pad_group);
+                }
+            }
+            zwp_tablet_pad_v2::Event::Buttons { buttons } => {
+                if let Some(pad) = state.tablet.pad_mut(pad) {
+                    pad.buttons = buttons;
+                }
+            }
+            zwp_tablet_pad_v2::Event::Done => {
+                let Some((seat, buttons)) = state
+                    .tablet
+                    .pad_mut(pad)
+                    .filter(|pad| pad.entity.is_none())
+                    .map(|pad| (pad.seat.clone(), pad.buttons))
+                else {
+                    return;
+                };
+                let Some(seat) = state.seat_entity(&seat) else {
+                    return;
+                };
+                let entity = state
+                    .world_mut()
+                    .spawn(TabletPad {
+                        seat,
+                        buttons,
+                        pressed: Vec::new(),
+                    })
+                    .id();
+                if let Some(pad) = state.tablet.pad_mut(pad) {
+                    pad.entity = Some(entity);
+                }
+            }
+            zwp_tablet_pad_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                let Some((entity, window)) = state
+                    .tablet
+                    .pad_mut(pad)
+                    .and_then(|pad| Some((pad.entity?, pad.focus)))
+                else {
+                    return;
+                };
+                let button_state =
+                    convert_button_state(button_state, zwp_tablet_pad_v2::ButtonState::Pressed);
+                let world = state.world_mut();
+                if let Some(mut pad) = world.get_mut::<TabletPad>(entity) {
+                    pad.pressed.retain(|pressed| *pressed != button);
+                    if button_state == ButtonState::Pressed {
+                        pad.pressed.push(button);
+                    }
+                }
+                world.send_event(TabletPadButtonInput {
+                    pad: entity,
+                    window,
+                    button,
+                    state: button_state,
+                });
+            }
+            zwp_tablet_pad_v2::Event::Enter { surface, .. } => {
+                let focus = state.surface_entity(&surface);
+                if let Some(pad) = state.tablet.pad_mut(pad) {
+                    pad.focus = focus;
+                }
+            }
+            zwp_tablet_pad_v2::Event::Leave { .. } => {
+                if let Some(pad) = state.tablet.pad_mut(pad) {
+                    pad.focus = None;
+                }
+            }
+            zwp_tablet_pad_v2::Event::Removed => {
+                let Some(index) = state.tablet.pads.iter().position(|other| &other.pad == pad)
+                else {
+                    return;
+                };
+                let pad = state.tablet.pads.remove(index);
+                state.despawn_pad(pad);
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(SmithayRunnerState, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<ZwpTabletPadGroupV2, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        group: &ZwpTabletPadGroupV2,
+        event: zwp_tablet_pad_group_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let control = match event {
+            zwp_tablet_pad_group_v2::Event::Ring { ring } => PadControl::Ring(ring),
+            zwp_tablet_pad_group_v2::Event::Strip { strip } => PadControl::Strip(strip),
+            zwp_tablet_pad_group_v2::Event::Dial { dial } => PadControl::Dial(dial),
+            _ => return,
+        };
+        if let Some(pad) = state
+            .tablet
+            .pads
+            .iter_mut()
+            .find(|pad| pad.groups.contains(group))
+        {
+            pad.controls.push(control);
+        }
+    }
+
+    event_created_child!(SmithayRunnerState, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, ()),
+        zwp_tablet_pad_group_v2::EVT_DIAL_OPCODE => (ZwpTabletPadDialV2, ()),
+    ]);
+}
+
+// Rings, strips and dials are not exposed, only the pad buttons are.
+
+impl Dispatch<ZwpTabletPadRingV2, ()> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadRingV2,
+        _: zwp_tablet_pad_ring_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadStripV2, ()> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadStripV2,
+        _: zwp_tablet_pad_strip_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwpTabletPadDialV2, ()> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTabletPadDialV2,
+        _: zwp_tablet_pad_dial_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
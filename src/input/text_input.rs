@@ -0,0 +1,288 @@
+use bevy::{
+    math::Vec2,
+    prelude::*,
+    window::{Ime, Window},
+};
+use smithay_client_toolkit::{
+    globals::GlobalData,
+    reexports::{
+        client::{
+            Connection, Dispatch, Proxy, QueueHandle,
+            globals::GlobalList,
+            protocol::{wl_seat::WlSeat, wl_surface::WlSurface},
+        },
+        protocols::wp::text_input::zv3::client::{
+            zwp_text_input_manager_v3::ZwpTextInputManagerV3,
+            zwp_text_input_v3::{self, ContentHint, ContentPurpose, ZwpTextInputV3},
+        },
+    },
+};
+
+use crate::{AppSendEvent, state::SmithayRunnerState};
+
+/// Sent when the input method asks to delete text around the cursor, before the following
+/// [`Ime::Commit`] is inserted.
+///
+/// Lengths are in bytes of UTF-8 text. Bevy's [`Ime`] has no equivalent of this request.
+#[derive(Event, Debug, Clone)]
+pub struct ImeDeleteSurrounding {
+    pub window: Entity,
+    pub before_length: usize,
+    pub after_length: usize,
+}
+
+/// The size of the cursor rectangle around `ime_position`, which bevy_winit also uses as bevy only
+/// reports the position of the text cursor.
+const IME_CURSOR_SIZE: i32 = 10;
+
+struct Preedit {
+    text: String,
+    cursor: Option<(usize, usize)>,
+}
+
+/// The text input of a single seat.
+struct TextInput {
+    seat: WlSeat,
+    text_input: ZwpTextInputV3,
+    focus: Option<WlSurface>,
+    /// The `ime_enabled` and `ime_position` last sent to the compositor for the focused window.
+    applied: Option<(bool, Vec2)>,
+    pending_preedit: Option<Preedit>,
+    pending_commit: Option<String>,
+    pending_delete: Option<(usize, usize)>,
+    /// The number of commit requests, which `done` events acknowledge with their serial.
+    commits: u32,
+}
+
+impl TextInput {
+    fn commit(&mut self) {
+        self.text_input.commit();
+        self.commits = self.commits.wrapping_add(1);
+    }
+}
+
+impl Drop for TextInput {
+    fn drop(&mut self) {
+        self.text_input.destroy();
+    }
+}
+
+pub(crate) struct TextInputState {
+    manager: Option<ZwpTextInputManagerV3>,
+    text_inputs: Vec<TextInput>,
+}
+
+impl TextInputState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            manager: globals.bind(qh, 1..=1, GlobalData).ok(),
+            text_inputs: Vec::new(),
+        }
+    }
+
+    /// Creates the text input of a seat that gained a keyboard.
+    pub(crate) fn add_seat(&mut self, seat: &WlSeat, qh: &QueueHandle<SmithayRunnerState>) {
+        let Some(manager) = &self.manager else {
+            return;
+        };
+        if self.text_inputs.iter().any(|input| &input.seat == seat) {
+            return;
+        }
+        self.text_inputs.push(TextInput {
+            seat: seat.clone(),
+            text_input: manager.get_text_input(seat, qh, ()),
+            focus: None,
+            applied: None,
+            pending_preedit: None,
+            pending_commit: None,
+            pending_delete: None,
+            commits: 0,
+        });
+    }
+
+    /// Destroys the text input of a seat that lost its keyboard.
+    pub(crate) fn remove_seat(&mut self, seat: &WlSeat) {
+        self.text_inputs.retain(|input| &input.seat != seat);
+    }
+}
+
+impl SmithayRunnerState {
+    /// Enables and disables the text inputs to match `ime_enabled` of the focused windows and
+    /// reports their `ime_position` as the cursor rectangle.
+    pub(crate) fn update_text_inputs(&mut self) {
+        for index in 0..self.text_input.text_inputs.len() {
+            let input = &self.text_input.text_inputs[index];
+            let Some(entity) = input
+                .focus
+                .as_ref()
+                .and_then(|surface| self.surface_entity(surface))
+            else {
+                continue;
+            };
+            let Some(window) = self.world().get::<Window>(entity) else {
+                continue;
+            };
+            let wanted = (window.ime_enabled, window.ime_position);
+
+            let input = &mut self.text_input.text_inputs[index];
+            let was_enabled = input.applied.is_some_and(|(enabled, _)| enabled);
+            if input.applied == Some(wanted) {
+                continue;
+            }
+            input.applied = Some(wanted);
+
+            let (enabled, position) = wanted;
+            if enabled {
+                if !was_enabled {
+                    input.text_input.enable();
+                    input
+                        .text_input
+                        .set_content_type(ContentHint::None, ContentPurpose::Normal);
+                }
+                input.text_input.set_cursor_rectangle(
+                    position.x as i32,
+                    position.y as i32,
+                    IME_CURSOR_SIZE,
+                    IME_CURSOR_SIZE,
+                );
+                input.commit();
+                if !was_enabled {
+                    self.bevy_window_events
+                        .send(Ime::Enabled { window: entity });
+                }
+            } else if was_enabled {
+                input.text_input.disable();
+                input.commit();
+                self.bevy_window_events
+                    .send(Ime::Disabled { window: entity });
+            }
+        }
+    }
+
+    /// Sends the IME events accumulated since the last `done` event of the text input.
+    fn text_input_done(&mut self, index: usize) {
+        let input = &mut self.text_input.text_inputs[index];
+        let preedit = input.pending_preedit.take();
+        let commit = input.pending_commit.take();
+        let delete = input.pending_delete.take();
+        let Some(window) = input
+            .focus
+            .clone()
+            .and_then(|surface| self.surface_entity(&surface))
+        else {
+            return;
+        };
+
+        // Clear the preedit, unless it is replaced by a new one right away.
+        if commit.is_some() || delete.is_some() || preedit.is_none() {
+            self.bevy_window_events.send(Ime::Preedit {
+                window,
+                value: String::new(),
+                cursor: None,
+            });
+        }
+        if let Some((before_length, after_length)) = delete {
+            self.world_mut().send_event(ImeDeleteSurrounding {
+                window,
+                before_length,
+                after_length,
+            });
+        }
+        if let Some(value) = commit {
+            self.bevy_window_events.send(Ime::Commit { window, value });
+        }
+        if let Some(preedit) = preedit {
+            self.bevy_window_events.send(Ime::Preedit {
+                window,
+                value: preedit.text,
+                cursor: preedit.cursor,
+            });
+        }
+    }
+}
+
+impl Dispatch<ZwpTextInputManagerV3, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpTextInputManagerV3,
+        _: <ZwpTextInputManagerV3 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_text_input_manager_v3 has no events")
+    }
+}
+
+impl Dispatch<ZwpTextInputV3, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        text_input: &ZwpTextInputV3,
+        event: zwp_text_input_v3::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(index) = state
+            .text_input
+            .text_inputs
+            .iter()
+            .position(|input| &input.text_input == text_input)
+        else {
+            return;
+        };
+        let input = &mut state.text_input.text_inputs[index];
+
+        match event {
+            zwp_text_input_v3::Event::Enter { surface } => {
+                // The text input is disabled on enter and has to be enabled again.
+                input.focus = Some(surface);
+                input.applied = None;
+            }
+            zwp_text_input_v3::Event::Leave { .. } => {
+                let was_enabled = input.applied.is_some_and(|(enabled, _)| enabled);
+                let focus = input.focus.take();
+                input.applied = None;
+                input.pending_preedit = None;
+                input.pending_commit = None;
+                input.pending_delete = None;
+                if was_enabled {
+                    input.text_input.disable();
+                    input.commit();
+                    if let Some(window) = focus.and_then(|surface| state.surface_entity(&surface)) {
+                        state.bevy_window_events.send(Ime::Disabled { window });
+                    }
+                }
+            }
+            zwp_text_input_v3::Event::PreeditString {
+                text,
+                cursor_begin,
+                cursor_end,
+            } => {
+                // Negative cursor positions hide the cursor.
+                let cursor = usize::try_from(cursor_begin)
+                    .ok()
+                    .map(|begin| (begin, usize::try_from(cursor_end).unwrap_or(begin)));
+                input.pending_preedit = Some(Preedit {
+                    text: text.unwrap_or_default(),
+                    cursor,
+                });
+            }
+            zwp_text_input_v3::Event::CommitString { text } => {
+                input.pending_commit = Some(text.unwrap_or_default());
+            }
+            zwp_text_input_v3::Event::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => {
+                input.pending_delete = Some((before_length as usize, after_length as usize));
+            }
+            // Events sent before the compositor received our last commit are based on an outdated
+            // state, so they are held back until it catches up.
+            zwp_text_input_v3::Event::Done { serial } if serial == input.commits => {
+                state.text_input_done(index);
+            }
+            _ => {}
+        }
+    }
+}
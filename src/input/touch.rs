@@ -1,34 +1,115 @@
 use bevy::input::touch::{TouchInput, TouchPhase};
 use bevy::log::{self, warn};
 use bevy::math::Vec2;
+use bevy::prelude::{Entity, Event};
 use bevy::window::{Window, WindowEvent};
 use smithay_client_toolkit::reexports::client::Proxy;
 use smithay_client_toolkit::{
     delegate_touch,
     reexports::client::{
         Connection, QueueHandle,
-        protocol::{wl_surface::WlSurface, wl_touch::WlTouch},
+        protocol::{wl_seat::WlSeat, wl_surface::WlSurface, wl_touch::WlTouch},
     },
-    seat::touch::TouchHandler,
+    seat::touch::{TouchData, TouchHandler},
 };
 
-use crate::AppSendEvent;
 use crate::smithay_windows::SmithayWindows;
 use crate::state::SmithayRunnerState; // Needed for tracking active touches
 
+/// The contact area of a touch point, sent after its [`TouchInput`] whenever the touchscreen
+/// reports a new shape or orientation.
+///
+/// A touch frame reporting both the shape and orientation of a touch point sends two events, the
+/// last of which holds the complete contact area.
+///
+/// `wl_touch` reports no pressure, so the `force` of touchscreen events is always `None`.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct TouchContact {
+    /// The id of the touch point, matching its [`TouchInput`] events.
+    pub id: u64,
+    pub window: Entity,
+    /// The length of the major axis of the elliptical contact area, in logical pixels.
+    pub major: f32,
+    /// The length of the minor axis of the elliptical contact area, in logical pixels.
+    pub minor: f32,
+    /// The clockwise angle between the major axis and the window's y axis in degrees, if the
+    /// touchscreen reports it.
+    pub orientation: Option<f32>,
+}
+
+/// The contact area of an active touch point.
+pub(crate) struct ActiveContact {
+    contact: TouchContact,
+    scale_factor: f32,
+}
+
+impl SmithayRunnerState {
+    /// Returns the contact area of the touch point `id` of `seat`.
+    fn touch_contact_mut(&mut self, seat: &WlSeat, id: i32) -> Option<&mut ActiveContact> {
+        let (window, _) = *self.seat_devices(seat)?.active_touches.get(&id)?;
+        let scale_factor = self.world().get::<Window>(window)?.scale_factor();
+        let devices = self.seat_devices_mut(seat)?;
+        let touch_id = devices.touch_id(id);
+        let contact = devices
+            .touch_contacts
+            .entry(id)
+            .or_insert_with(|| ActiveContact {
+                contact: TouchContact {
+                    id: touch_id,
+                    window,
+                    major: 0.0,
+                    minor: 0.0,
+                    orientation: None,
+                },
+                scale_factor,
+            });
+        Some(contact)
+    }
+
+    /// Cancels all active touch points of `seat`.
+    pub(crate) fn cancel_touches(&mut self, seat: &WlSeat) {
+        let Some(devices) = self.seat_devices_mut(seat) else {
+            return;
+        };
+        devices.touch_contacts.clear();
+        devices.latest_press_serial = None;
+        let cancelled = std::mem::take(&mut devices.active_touches)
+            .into_iter()
+            .map(|(id, touch_data)| (devices.touch_id(id), touch_data))
+            .collect::<Vec<_>>();
+
+        for (touch_id, (entity, last_position)) in cancelled {
+            // Create and send the Bevy touch event
+            let bevy_event = TouchInput {
+                phase: TouchPhase::Canceled,
+                position: last_position, // Use the stored last position
+                force: None,
+                id: touch_id,
+                window: entity,
+            };
+            // Send the event
+            self.send_seat_input(Some(seat), WindowEvent::TouchInput(bevy_event));
+        }
+    }
+}
+
 impl TouchHandler for SmithayRunnerState {
     /// Handles the "down" event when a touch point is pressed on the surface.
     fn down(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _serial: u32,
+        touch: &WlTouch,
+        serial: u32,
         _time: u32,
         surface: WlSurface,
         id: i32,
         position: (f64, f64),
     ) {
+        let seat = touch.data::<TouchData>().map(|data| data.seat().clone());
+        self.set_latest_input_serial(seat.as_ref(), serial);
+        self.set_latest_press_serial(seat.as_ref(), serial);
+
         let window_entity = {
             let smithay_windows = self.world().non_send_resource::<SmithayWindows>();
             let window_id = surface.id();
@@ -58,8 +139,14 @@ impl TouchHandler for SmithayRunnerState {
         let logical_position = Vec2::new(position.0 as f32, position.1 as f32) / scale_factor;
 
         // Store the active touch point's entity and logical position
-        self.active_touches
+        let Some(devices) = seat.as_ref().and_then(|seat| self.seat_devices_mut(seat)) else {
+            warn!("touch down event for an unknown seat");
+            return;
+        };
+        devices
+            .active_touches
             .insert(id, (window_entity, logical_position));
+        let touch_id = devices.touch_id(id);
 
         // Create and send the Bevy touch event
         let bevy_event = TouchInput {
@@ -67,13 +154,12 @@ impl TouchHandler for SmithayRunnerState {
             position: logical_position,
             // Force is not directly available in basic Wayland touch events
             force: None,
-            id: id as u64, // Bevy uses u64 for touch IDs
+            id: touch_id, // Bevy uses u64 for touch IDs
             window: window_entity,
         };
 
-        // Send the event (adapt this line based on how you send events)
-        self.bevy_window_events
-            .send(WindowEvent::TouchInput(bevy_event));
+        // sctk holds back the events of a frame until it is complete, so send them right away
+        self.send_seat_input(seat.as_ref(), bevy_event);
     }
 
     /// Handles the "up" event when a touch point is released from the surface.
@@ -81,13 +167,24 @@ impl TouchHandler for SmithayRunnerState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _touch: &WlTouch,
+        touch: &WlTouch,
         _serial: u32,
         _time: u32,
         id: i32,
     ) {
+        let seat = touch.data::<TouchData>().map(|data| data.seat().clone());
+        let Some(devices) = seat.as_ref().and_then(|seat| self.seat_devices_mut(seat)) else {
+            warn!("touch up event for an unknown seat");
+            return;
+        };
         // Retrieve the entity and last known position for the touch ID, then remove it
-        let touch_data = self.active_touches.remove(&id);
+        let touch_data = devices.active_touches.remove(&id);
+        devices.touch_contacts.remove(&id);
+        let touch_id = devices.touch_id(id);
+        // The implicit grab lasts until the last touch point is lifted.
+        if devices.active_touches.is_empty() {
+            devices.latest_press_serial = None;
+        }
 
         if let Some((entity, last_position)) = touch_data {
             // Create and send the Bevy touch event
@@ -95,12 +192,11 @@ impl TouchHandler for SmithayRunnerState {
                 phase: TouchPhase::Ended,
                 position: last_position, // Use the stored last position
                 force: None,
-                id: id as u64,
+                id: touch_id,
                 window: entity,
             };
             // Send the event
-            self.bevy_window_events
-                .send(WindowEvent::TouchInput(bevy_event));
+            self.send_seat_input(seat.as_ref(), bevy_event);
         } else {
             // This might happen if the 'down' event was missed or occurred on a different surface
             log::warn!(
@@ -115,13 +211,19 @@ impl TouchHandler for SmithayRunnerState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _touch: &WlTouch,
+        touch: &WlTouch,
         _time: u32,
         id: i32,
         position: (f64, f64),
     ) {
+        let seat = touch.data::<TouchData>().map(|data| data.seat().clone());
+        let Some(devices) = seat.as_ref().and_then(|seat| self.seat_devices(seat)) else {
+            warn!("touch motion event for an unknown seat");
+            return;
+        };
+        let touch_id = devices.touch_id(id);
         // Get the entity associated with this ongoing touch ID
-        let entity = if let Some((entity, _)) = self.active_touches.get(&id).copied() {
+        let entity = if let Some((entity, _)) = devices.active_touches.get(&id).copied() {
             entity
         } else {
             warn!("touch motion event for unknown touch ID: {}", id);
@@ -144,7 +246,11 @@ impl TouchHandler for SmithayRunnerState {
         let logical_position = Vec2::new(position.0 as f32, position.1 as f32) / scale_factor;
 
         // Update the stored position for the touch ID
-        if let Some(touch_data) = self.active_touches.get_mut(&id) {
+        if let Some(touch_data) = seat
+            .as_ref()
+            .and_then(|seat| self.seat_devices_mut(seat))
+            .and_then(|devices| devices.active_touches.get_mut(&id))
+        {
             touch_data.1 = logical_position;
         } else {
             warn!("touch motion event occurred without a touch down event");
@@ -156,40 +262,21 @@ impl TouchHandler for SmithayRunnerState {
             phase: TouchPhase::Moved,
             position: logical_position,
             force: None,
-            id: id as u64,
+            id: touch_id,
             window: entity,
         };
 
         // Send the event
-        self.bevy_window_events
-            .send(WindowEvent::TouchInput(bevy_touch_event));
+        self.send_seat_input(seat.as_ref(), bevy_touch_event);
     }
 
     /// Handles the "cancel" event when a touch sequence is canceled.
-    fn cancel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, _touch: &WlTouch) {
-        for id in self.active_touches.keys().copied().collect::<Vec<_>>() {
-            let touch_data = self.active_touches.remove(&id);
-
-            if let Some((entity, last_position)) = touch_data {
-                // Create and send the Bevy touch event
-                let bevy_event = TouchInput {
-                    phase: TouchPhase::Canceled,
-                    position: last_position, // Use the stored last position
-                    force: None,
-                    id: id as u64,
-                    window: entity,
-                };
-                // Send the event
-                self.bevy_window_events
-                    .send(WindowEvent::TouchInput(bevy_event));
-            } else {
-                warn!(
-                    "touch cancel event for unknown or already removed touch ID: {}",
-                    id
-                );
-                return;
-            }
-        }
+    fn cancel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, touch: &WlTouch) {
+        let Some(seat) = touch.data::<TouchData>().map(|data| data.seat().clone()) else {
+            warn!("touch cancel event for an unknown seat");
+            return;
+        };
+        self.cancel_touches(&seat);
     }
 
     /// Handles the "shape" event when the shape of a touch point changes.
@@ -197,12 +284,25 @@ impl TouchHandler for SmithayRunnerState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _id: i32,
-        _major: f64,
-        _minor: f64,
+        touch: &WlTouch,
+        id: i32,
+        major: f64,
+        minor: f64,
     ) {
-        // Handle touch shape change if needed
+        let seat = touch.data::<TouchData>().map(|data| data.seat().clone());
+        let Some(contact) = seat
+            .as_ref()
+            .and_then(|seat| self.touch_contact_mut(seat, id))
+        else {
+            warn!("touch shape event for unknown touch ID: {}", id);
+            return;
+        };
+        // The axes are given in surface coordinates, like the position.
+        let scale_factor = contact.scale_factor;
+        contact.contact.major = major as f32 / scale_factor;
+        contact.contact.minor = minor as f32 / scale_factor;
+        let contact = contact.contact;
+        self.world_mut().send_event(contact);
     }
 
     /// Handles the "orientation" event when the orientation of a touch point changes.
@@ -210,11 +310,21 @@ impl TouchHandler for SmithayRunnerState {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _touch: &WlTouch,
-        _id: i32,
-        _orientation: f64,
+        touch: &WlTouch,
+        id: i32,
+        orientation: f64,
     ) {
-        // Handle touch orientation change if needed
+        let seat = touch.data::<TouchData>().map(|data| data.seat().clone());
+        let Some(contact) = seat
+            .as_ref()
+            .and_then(|seat| self.touch_contact_mut(seat, id))
+        else {
+            warn!("touch orientation event for unknown touch ID: {}", id);
+            return;
+        };
+        contact.contact.orientation = Some(orientation as f32);
+        let contact = contact.contact;
+        self.world_mut().send_event(contact);
     }
 }
 
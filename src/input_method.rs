@@ -0,0 +1,573 @@
+//! Support for writing input methods, e.g. on-screen keyboards, using `zwp_input_method_v2` and
+//! `zwp_virtual_keyboard_v1`.
+//!
+//! The [`InputMethod`] resource sends text and key events to the client focused on the first seat,
+//! while [`InputMethodActivated`], [`InputMethodDeactivated`], [`SurroundingTextChanged`] and
+//! [`ContentTypeChanged`] report the state of that client's text field.
+//!
+//! A seat has a single input method, so this is only enabled by adding the [`InputMethodPlugin`]
+//! along with the `SmithayPlugin`.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    os::fd::AsFd,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{input::ButtonState, prelude::*};
+use smithay_client_toolkit::{
+    globals::GlobalData,
+    reexports::client::{
+        Connection, Dispatch, Proxy, QueueHandle, WEnum, globals::GlobalList,
+        protocol::wl_seat::WlSeat,
+    },
+};
+pub use smithay_client_toolkit::reexports::protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ContentHint, ContentPurpose,
+};
+use wayland_protocols_misc::{
+    zwp_input_method_v2::client::{
+        zwp_input_method_manager_v2::ZwpInputMethodManagerV2,
+        zwp_input_method_v2::{self, ZwpInputMethodV2},
+    },
+    zwp_virtual_keyboard_v1::client::{
+        zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
+        zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+    },
+};
+use xkbcommon::xkb;
+
+use crate::state::SmithayRunnerState;
+
+/// Makes the app the input method of the first seat, e.g. to implement an on-screen keyboard.
+///
+/// This takes over the seat's input method from any other client, like the user's on-screen
+/// keyboard or IME.
+pub struct InputMethodPlugin;
+
+impl Plugin for InputMethodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMethod>()
+            .add_event::<InputMethodActivated>()
+            .add_event::<InputMethodDeactivated>()
+            .add_event::<SurroundingTextChanged>()
+            .add_event::<ContentTypeChanged>();
+    }
+}
+
+/// Sends text and key events to the client focused by the input method.
+///
+/// Text requests only take effect while the input method is active, see [`InputMethodActivated`].
+/// Key events are sent through a virtual keyboard using the default XKB keymap, configured through
+/// the `XKB_DEFAULT_*` environment variables.
+#[derive(Resource, Debug, Default)]
+pub struct InputMethod {
+    active: bool,
+    available: bool,
+    surrounding_text: Option<SurroundingText>,
+    content_type: Option<(ContentHint, ContentPurpose)>,
+    requests: Vec<InputMethodRequest>,
+}
+
+impl InputMethod {
+    /// Returns `true` if a text field of the focused client accepts input.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns `true` if the compositor provides an input method to this client.
+    ///
+    /// Only one client can be the input method of a seat at a time.
+    pub fn is_available(&self) -> bool {
+        self.available
+    }
+
+    /// Returns the text around the cursor of the focused text field, if the client reports it.
+    pub fn surrounding_text(&self) -> Option<&SurroundingText> {
+        self.surrounding_text.as_ref()
+    }
+
+    /// Returns the content hint and purpose of the focused text field, if the client reports it.
+    pub fn content_type(&self) -> Option<(ContentHint, ContentPurpose)> {
+        self.content_type
+    }
+
+    /// Inserts `text` at the cursor, replacing the preedit text.
+    pub fn commit_string(&mut self, text: impl Into<String>) {
+        self.requests
+            .push(InputMethodRequest::CommitString(text.into()));
+    }
+
+    /// Shows `text` as preedit text at the cursor.
+    ///
+    /// `cursor` is the byte range of the cursor within `text`, `None` hides the cursor.
+    pub fn set_preedit_string(&mut self, text: impl Into<String>, cursor: Option<(usize, usize)>) {
+        self.requests
+            .push(InputMethodRequest::SetPreeditString(text.into(), cursor));
+    }
+
+    /// Deletes `before_length` bytes before and `after_length` bytes after the cursor.
+    pub fn delete_surrounding_text(&mut self, before_length: usize, after_length: usize) {
+        self.requests
+            .push(InputMethodRequest::DeleteSurroundingText(
+                before_length,
+                after_length,
+            ));
+    }
+
+    /// Presses or releases the key with the evdev keycode `raw_code` on the virtual keyboard.
+    pub fn send_key(&mut self, raw_code: u32, state: ButtonState) {
+        self.requests.push(InputMethodRequest::Key(raw_code, state));
+    }
+
+    /// Sets the modifier state of the virtual keyboard, as masks of the default keymap.
+    pub fn send_modifiers(&mut self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        self.requests.push(InputMethodRequest::Modifiers {
+            depressed,
+            latched,
+            locked,
+            group,
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InputMethodRequest {
+    CommitString(String),
+    SetPreeditString(String, Option<(usize, usize)>),
+    DeleteSurroundingText(usize, usize),
+    Key(u32, ButtonState),
+    Modifiers {
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+    },
+}
+
+/// The text around the cursor of the focused text field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurroundingText {
+    pub text: String,
+    /// The byte offset of the cursor in `text`.
+    pub cursor: usize,
+    /// The byte offset of the other end of the selection in `text`, equal to `cursor` without a
+    /// selection.
+    pub anchor: usize,
+}
+
+/// Sent when a text field of the focused client starts accepting input.
+#[derive(Event, Debug, Clone)]
+pub struct InputMethodActivated;
+
+/// Sent when the focused text field stops accepting input.
+#[derive(Event, Debug, Clone)]
+pub struct InputMethodDeactivated;
+
+/// Sent when the text around the cursor of the focused text field changes.
+#[derive(Event, Debug, Clone)]
+pub struct SurroundingTextChanged(pub SurroundingText);
+
+/// Sent when the content hint or purpose of the focused text field changes.
+#[derive(Event, Debug, Clone)]
+pub struct ContentTypeChanged {
+    pub hint: ContentHint,
+    pub purpose: ContentPurpose,
+}
+
+/// State received since the last `done` event, applied once it arrives.
+#[derive(Default)]
+struct PendingState {
+    active: bool,
+    surrounding_text: Option<SurroundingText>,
+    content_type: Option<(ContentHint, ContentPurpose)>,
+}
+
+struct SeatInputMethod {
+    input_method: ZwpInputMethodV2,
+    /// Created along with the first key event.
+    virtual_keyboard: Option<ZwpVirtualKeyboardV1>,
+    /// Set if the virtual keyboard could not be created, to not try again on every key.
+    virtual_keyboard_failed: bool,
+    pending: PendingState,
+    /// The number of `done` events received, acknowledged by every commit.
+    serial: u32,
+}
+
+pub(crate) struct InputMethodState {
+    input_method_manager: Option<ZwpInputMethodManagerV2>,
+    virtual_keyboard_manager: Option<ZwpVirtualKeyboardManagerV1>,
+    seat: Option<(WlSeat, SeatInputMethod)>,
+    start: Instant,
+}
+
+impl InputMethodState {
+    pub(crate) fn new(globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) -> Self {
+        Self {
+            input_method_manager: globals.bind(qh, 1..=1, GlobalData).ok(),
+            virtual_keyboard_manager: globals.bind(qh, 1..=1, GlobalData).ok(),
+            seat: None,
+            start: Instant::now(),
+        }
+    }
+
+    /// Creates the input method for the first seat.
+    pub(crate) fn add_seat(&mut self, seat: &WlSeat, qh: &QueueHandle<SmithayRunnerState>) {
+        if self.seat.is_some() {
+            return;
+        }
+        let Some(input_method_manager) = &self.input_method_manager else {
+            return;
+        };
+
+        self.seat = Some((
+            seat.clone(),
+            SeatInputMethod {
+                input_method: input_method_manager.get_input_method(seat, qh, ()),
+                virtual_keyboard: None,
+                virtual_keyboard_failed: false,
+                pending: PendingState::default(),
+                serial: 0,
+            },
+        ));
+    }
+
+    /// Destroys the input method of a removed seat.
+    pub(crate) fn remove_seat(&mut self, seat: &WlSeat) {
+        if !self.seat.as_ref().is_some_and(|(s, _)| s == seat) {
+            return;
+        }
+        if let Some((_, seat_input_method)) = self.seat.take() {
+            seat_input_method.input_method.destroy();
+            if let Some(virtual_keyboard) = seat_input_method.virtual_keyboard {
+                virtual_keyboard.destroy();
+            }
+        }
+    }
+}
+
+impl SeatInputMethod {
+    /// Returns the virtual keyboard of `seat`, creating it on first use.
+    fn virtual_keyboard(
+        &mut self,
+        seat: &WlSeat,
+        manager: Option<&ZwpVirtualKeyboardManagerV1>,
+        qh: &QueueHandle<SmithayRunnerState>,
+    ) -> Option<&ZwpVirtualKeyboardV1> {
+        if self.virtual_keyboard.is_none() && !self.virtual_keyboard_failed {
+            let virtual_keyboard = manager?.create_virtual_keyboard(seat, qh, ());
+            match upload_default_keymap(&virtual_keyboard) {
+                Ok(()) => self.virtual_keyboard = Some(virtual_keyboard),
+                Err(err) => {
+                    warn!("failed to set the virtual keyboard keymap: {}", err);
+                    virtual_keyboard.destroy();
+                    self.virtual_keyboard_failed = true;
+                }
+            }
+        }
+        self.virtual_keyboard.as_ref()
+    }
+}
+
+/// Compiles the default XKB keymap and sends it to the virtual keyboard.
+fn upload_default_keymap(virtual_keyboard: &ZwpVirtualKeyboardV1) -> std::io::Result<()> {
+    let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+    let keymap = xkb::Keymap::new_from_names(&context, "", "", "", "", None, xkb::COMPILE_NO_FLAGS)
+        .ok_or_else(|| std::io::Error::other("failed to compile the default keymap"))?;
+    let mut keymap = keymap
+        .get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1)
+        .into_bytes();
+    keymap.push(0);
+
+    let file = keymap_file()?;
+    (&file).write_all(&keymap)?;
+    virtual_keyboard.keymap(
+        xkb::KEYMAP_FORMAT_TEXT_V1,
+        file.as_fd(),
+        keymap.len() as u32,
+    );
+    Ok(())
+}
+
+/// Creates an anonymous file to share the keymap with the compositor.
+fn keymap_file() -> std::io::Result<File> {
+    #[cfg(target_os = "linux")]
+    {
+        use rustix::{fs::MemfdFlags, io::Errno};
+
+        match rustix::fs::memfd_create(c"bevy_smithay-keymap", MemfdFlags::CLOEXEC) {
+            Ok(fd) => return Ok(fd.into()),
+            // Not supported, use an unlinked file instead.
+            Err(Errno::NOSYS) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(Into::into)
+        .unwrap_or_else(std::env::temp_dir);
+    loop {
+        // A stale file of a crashed process must not block the upload, so pick a fresh name.
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let path = dir.join(format!(
+            "bevy_smithay-keymap-{}-{nanos}",
+            std::process::id()
+        ));
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => {
+                std::fs::remove_file(&path)?;
+                return Ok(file);
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+impl SmithayRunnerState {
+    /// Creates the input method of a new seat and reports whether one is available.
+    ///
+    /// Does nothing unless the [`InputMethodPlugin`] was added.
+    pub(crate) fn add_input_method_seat(
+        &mut self,
+        seat: &WlSeat,
+        qh: &QueueHandle<SmithayRunnerState>,
+    ) {
+        if !self.world().contains_resource::<InputMethod>() {
+            return;
+        }
+        self.input_method.add_seat(seat, qh);
+        let available = self.input_method.seat.is_some();
+        self.world_mut().resource_mut::<InputMethod>().available = available;
+    }
+
+    /// Destroys the input method of a removed seat, or one that became unavailable, deactivating it
+    /// first.
+    pub(crate) fn remove_input_method_seat(&mut self, seat: &WlSeat) {
+        self.input_method.remove_seat(seat);
+        if self.input_method.seat.is_some() {
+            return;
+        }
+        let world = self.world_mut();
+        let Some(mut input_method) = world.get_resource_mut::<InputMethod>() else {
+            return;
+        };
+        let was_active = input_method.active;
+        *input_method = InputMethod::default();
+        if was_active {
+            world.send_event(InputMethodDeactivated);
+        }
+    }
+
+    /// Sends the requests made through the [`InputMethod`] resource.
+    pub(crate) fn update_input_method(&mut self, qh: &QueueHandle<Self>) {
+        let Some(mut input_method) = self.world_mut().get_resource_mut::<InputMethod>() else {
+            return;
+        };
+        let requests = std::mem::take(&mut input_method.requests);
+        if requests.is_empty() {
+            return;
+        }
+        let Some((seat, seat_input_method)) = &mut self.input_method.seat else {
+            return;
+        };
+        let virtual_keyboard_manager = self.input_method.virtual_keyboard_manager.as_ref();
+        let time = self.input_method.start.elapsed().as_millis() as u32;
+
+        let mut commit = false;
+        for request in requests {
+            let input_method = &seat_input_method.input_method.clone();
+            match request {
+                InputMethodRequest::CommitString(text) => {
+                    input_method.commit_string(text);
+                    commit = true;
+                }
+                InputMethodRequest::SetPreeditString(text, cursor) => {
+                    let (begin, end) =
+                        cursor.map_or((-1, -1), |(begin, end)| (begin as i32, end as i32));
+                    input_method.set_preedit_string(text, begin, end);
+                    commit = true;
+                }
+                InputMethodRequest::DeleteSurroundingText(before_length, after_length) => {
+                    input_method.delete_surrounding_text(before_length as u32, after_length as u32);
+                    commit = true;
+                }
+                InputMethodRequest::Key(raw_code, state) => {
+                    if let Some(virtual_keyboard) =
+                        seat_input_method.virtual_keyboard(seat, virtual_keyboard_manager, qh)
+                    {
+                        let state = match state {
+                            ButtonState::Pressed => 1,
+                            ButtonState::Released => 0,
+                        };
+                        virtual_keyboard.key(time, raw_code, state);
+                    }
+                }
+                InputMethodRequest::Modifiers {
+                    depressed,
+                    latched,
+                    locked,
+                    group,
+                } => {
+                    if let Some(virtual_keyboard) =
+                        seat_input_method.virtual_keyboard(seat, virtual_keyboard_manager, qh)
+                    {
+                        virtual_keyboard.modifiers(depressed, latched, locked, group);
+                    }
+                }
+            }
+        }
+        if commit {
+            seat_input_method
+                .input_method
+                .commit(seat_input_method.serial);
+        }
+    }
+
+    /// Applies the state received since the last `done` event.
+    fn input_method_done(&mut self) {
+        let Some((_, seat_input_method)) = &mut self.input_method.seat else {
+            return;
+        };
+        seat_input_method.serial = seat_input_method.serial.wrapping_add(1);
+        let pending = &seat_input_method.pending;
+        let (active, surrounding_text, content_type) = (
+            pending.active,
+            pending.surrounding_text.clone(),
+            pending.content_type,
+        );
+
+        let world = self.world_mut();
+        let mut input_method = world.resource_mut::<InputMethod>();
+        let was_active = input_method.active;
+        let surrounding_text_changed = input_method.surrounding_text != surrounding_text;
+        let content_type_changed = input_method.content_type != content_type;
+        input_method.active = active;
+        input_method.surrounding_text = surrounding_text.clone();
+        input_method.content_type = content_type;
+
+        if active && !was_active {
+            world.send_event(InputMethodActivated);
+        }
+        if surrounding_text_changed && let Some(surrounding_text) = surrounding_text {
+            world.send_event(SurroundingTextChanged(surrounding_text));
+        }
+        if content_type_changed && let Some((hint, purpose)) = content_type {
+            world.send_event(ContentTypeChanged { hint, purpose });
+        }
+        if !active && was_active {
+            world.send_event(InputMethodDeactivated);
+        }
+    }
+}
+
+impl Dispatch<ZwpInputMethodManagerV2, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpInputMethodManagerV2,
+        _: <ZwpInputMethodManagerV2 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_input_method_manager_v2 has no events")
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardManagerV1, GlobalData> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpVirtualKeyboardManagerV1,
+        _: <ZwpVirtualKeyboardManagerV1 as Proxy>::Event,
+        _: &GlobalData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_virtual_keyboard_manager_v1 has no events")
+    }
+}
+
+impl Dispatch<ZwpVirtualKeyboardV1, ()> for SmithayRunnerState {
+    fn event(
+        _: &mut Self,
+        _: &ZwpVirtualKeyboardV1,
+        _: <ZwpVirtualKeyboardV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        unreachable!("zwp_virtual_keyboard_v1 has no events")
+    }
+}
+
+impl Dispatch<ZwpInputMethodV2, ()> for SmithayRunnerState {
+    fn event(
+        state: &mut Self,
+        _: &ZwpInputMethodV2,
+        event: zwp_input_method_v2::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwp_input_method_v2::Event::Done = event {
+            state.input_method_done();
+            return;
+        }
+        if let zwp_input_method_v2::Event::Unavailable = event {
+            warn!("the input method is already provided by another client");
+            // The object is inert from now on, so drop it like the input method of a removed seat.
+            if let Some(seat) = state
+                .input_method
+                .seat
+                .as_ref()
+                .map(|(seat, _)| seat.clone())
+            {
+                state.remove_input_method_seat(&seat);
+            }
+            return;
+        }
+
+        let Some((_, seat_input_method)) = &mut state.input_method.seat else {
+            return;
+        };
+        let pending = &mut seat_input_method.pending;
+        match event {
+            zwp_input_method_v2::Event::Activate => {
+                // Activation resets the state of the text field.
+                *pending = PendingState {
+                    active: true,
+                    ..default()
+                };
+            }
+            zwp_input_method_v2::Event::Deactivate => {
+                pending.active = false;
+            }
+            zwp_input_method_v2::Event::SurroundingText {
+                text,
+                cursor,
+                anchor,
+            } => {
+                pending.surrounding_text = Some(SurroundingText {
+                    text,
+                    cursor: cursor as usize,
+                    anchor: anchor as usize,
+                });
+            }
+            zwp_input_method_v2::Event::ContentType { hint, purpose } => {
+                if let (WEnum::Value(hint), WEnum::Value(purpose)) = (hint, purpose) {
+                    pending.content_type = Some((hint, purpose));
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -2,10 +2,28 @@ use bevy::{
     prelude::*,
     window::{PrimaryWindow, RawHandleWrapperHolder, WindowCreated},
 };
-use shells::layer_shell::LayerShellSettings;
+use clipboard::{Clipboard, ClipboardChanged, ClipboardRead};
+use idle::{IdleTimeouts, UserIdle, UserResumed};
+use input::{
+    drag_and_drop::{DragActionChanged, DragAndDrop, DragCancelled, DragDropped, DragFinished},
+    keyboard::KeyboardModifiers,
+    pointer_constraints::{CursorGrabActivated, CursorGrabDeactivated},
+    seat::{InputCapabilities, InputDeviceAdded, InputDeviceRemoved, SeatInput},
+    tablet::{TabletPadButtonInput, TabletToolInput},
+    text_input::ImeDeleteSurrounding,
+    touch::TouchContact,
+};
+use shells::{
+    layer_shell::LayerShellSettings,
+    session_lock::{SessionLock, SessionLockFinished, SessionLocked},
+};
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
 use smithay_windows::SmithayWindows;
 
+pub mod clipboard;
+pub mod idle;
 mod input;
+pub mod input_method;
 mod shells;
 mod smithay_windows;
 mod state;
@@ -13,9 +31,38 @@ mod system;
 
 pub mod prelude {
     pub use super::SmithayPlugin;
+    pub use super::clipboard;
+    pub use super::idle;
+    pub use super::input::cursor::{
+        AnimatedCursor, CustomCursor, CustomCursorImage, SmithayCursorIcon,
+    };
+    pub use super::input::drag_and_drop::{
+        DragAction, DragActionChanged, DragAndDrop, DragCancelled, DragDropped, DragFinished,
+        DragIcon, DragId, StartDrag,
+    };
+    pub use super::input::keyboard::KeyboardModifiers;
+    pub use super::input::pointer_constraints::{
+        CursorGrabActivated, CursorGrabDeactivated, CursorGrabRegion,
+    };
+    pub use super::input::seat::{
+        InputCapabilities, InputDeviceAdded, InputDeviceRemoved, Seat, SeatCapabilities,
+        SeatCapability, SeatInput,
+    };
+    pub use super::input::tablet::{
+        TabletPad, TabletPadButtonInput, TabletTool, TabletToolEvent, TabletToolInput,
+        TabletToolType,
+    };
+    pub use super::input::text_input::ImeDeleteSurrounding;
+    pub use super::input::touch::TouchContact;
+    pub use super::input_method;
     pub use super::shells::*;
 }
 
+/// Runs the app on a Wayland compositor through smithay-client-toolkit, in place of `bevy_winit`.
+///
+/// Components of `bevy_winit` have no effect on the windows of this plugin. In particular, the
+/// cursor of a window is set with [`SmithayCursorIcon`](prelude::SmithayCursorIcon) instead of
+/// bevy's `CursorIcon`.
 pub struct SmithayPlugin {
     pub primary_window_type: SmithayWindowType,
 }
@@ -37,6 +84,32 @@ impl Plugin for SmithayPlugin {
 
     fn build(&self, app: &mut App) {
         app.init_non_send_resource::<SmithayWindows>()
+            .init_resource::<SessionLock>()
+            .add_event::<SessionLocked>()
+            .add_event::<SessionLockFinished>()
+            .init_resource::<IdleTimeouts>()
+            .add_event::<UserIdle>()
+            .add_event::<UserResumed>()
+            .add_event::<CursorGrabActivated>()
+            .add_event::<CursorGrabDeactivated>()
+            .add_event::<SeatInput>()
+            .init_resource::<InputCapabilities>()
+            .add_event::<InputDeviceAdded>()
+            .add_event::<InputDeviceRemoved>()
+            .add_event::<TabletToolInput>()
+            .add_event::<TabletPadButtonInput>()
+            .add_event::<TouchContact>()
+            .init_resource::<KeyboardModifiers>()
+            .add_event::<ImeDeleteSurrounding>()
+            .init_resource::<Clipboard>()
+            .add_event::<ClipboardRead>()
+            .add_event::<ClipboardChanged>()
+            .init_resource::<DragAndDrop>()
+            .add_event::<DragActionChanged>()
+            .add_event::<DragDropped>()
+            .add_event::<DragFinished>()
+            .add_event::<DragCancelled>()
+            .add_systems(PostUpdate, clipboard::sync_primary_selection)
             .add_systems(Last, (system::changed_windows, system::despawn_windows));
         let query = app
             .world()
@@ -62,6 +135,13 @@ pub enum SmithayWindowType {
         parent: Entity,
         position: (i32, i32),
     },
+    /// A lock surface covering `output` while the session is locked.
+    ///
+    /// These windows are spawned by the runner when locking through
+    /// [`SessionLock`](shells::session_lock::SessionLock).
+    SessionLock {
+        output: WlOutput,
+    },
 }
 
 impl Default for SmithayWindowType {
@@ -1,2 +1,3 @@
 pub mod layer_shell;
+pub mod session_lock;
 pub mod subsurface;
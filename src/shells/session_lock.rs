@@ -0,0 +1,307 @@
+use bevy::prelude::*;
+use raw_window_handle::{
+    DisplayHandle, HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle,
+    WaylandDisplayHandle, WaylandWindowHandle, WindowHandle,
+};
+use smithay_client_toolkit::{
+    delegate_session_lock,
+    reexports::client::{
+        Connection, Proxy, QueueHandle,
+        protocol::{wl_output::WlOutput, wl_surface::WlSurface},
+    },
+    session_lock::{
+        self as sctk_session_lock, SessionLockHandler, SessionLockSurface,
+        SessionLockSurfaceConfigure,
+    },
+};
+
+use crate::{SmithayWindowType, smithay_windows::SmithayWindows, state::SmithayRunnerState};
+
+/// Controls the `ext_session_lock_v1` lock of the current session.
+///
+/// While the session is locked, the runner creates one lock surface window per output and keeps
+/// that set in sync with output hotplug. Lock windows are regular window entities carrying
+/// [`SmithayWindowType::SessionLock`], so they can be rendered to like any other window.
+#[derive(Resource, Debug, Default)]
+pub struct SessionLock {
+    status: SessionLockStatus,
+    request: Option<SessionLockRequest>,
+}
+
+impl SessionLock {
+    /// Requests the compositor to lock the session.
+    ///
+    /// [`SessionLocked`] is sent once the compositor confirms the lock.
+    pub fn lock(&mut self) {
+        self.request = Some(SessionLockRequest::Lock);
+    }
+
+    /// Unlocks the session and destroys all lock surface windows.
+    ///
+    /// While the lock is pending, the unlock waits until the compositor confirms or denies it.
+    pub fn unlock(&mut self) {
+        self.request = Some(SessionLockRequest::Unlock);
+    }
+
+    /// Returns the current state of the session lock.
+    pub fn status(&self) -> SessionLockStatus {
+        self.status
+    }
+
+    /// Returns `true` if the compositor confirmed that the session is locked.
+    pub fn is_locked(&self) -> bool {
+        self.status == SessionLockStatus::Locked
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLockStatus {
+    /// The session is not locked by this client.
+    #[default]
+    Unlocked,
+    /// A lock was requested and the compositor has not confirmed it yet.
+    Pending,
+    /// The compositor confirmed that the session is locked.
+    Locked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionLockRequest {
+    Lock,
+    Unlock,
+}
+
+/// Sent when the compositor confirms that the session is locked.
+#[derive(Event, Debug, Clone)]
+pub struct SessionLocked;
+
+/// Sent when the compositor ends the session lock, either because it denied the lock or because
+/// another client unlocked the session.
+#[derive(Event, Debug, Clone)]
+pub struct SessionLockFinished;
+
+pub struct SessionLockWindow {
+    window: Option<SessionLockSurface>,
+    output: WlOutput,
+    conn: Connection,
+}
+
+impl SessionLockWindow {
+    pub fn lock_surface(&self) -> &SessionLockSurface {
+        self.window
+            .as_ref()
+            .expect("trying to access lock surface after destroying")
+    }
+
+    pub fn output(&self) -> &WlOutput {
+        &self.output
+    }
+}
+
+impl HasWindowHandle for SessionLockWindow {
+    fn window_handle(
+        &self,
+    ) -> Result<raw_window_handle::WindowHandle<'_>, raw_window_handle::HandleError> {
+        let raw_window_handle = RawWindowHandle::Wayland(WaylandWindowHandle::new(
+            core::ptr::NonNull::new(
+                self.window
+                    .as_ref()
+                    .expect("window handles doesn't exist because surface was destroyed")
+                    .wl_surface()
+                    .id()
+                    .as_ptr() as *mut _,
+            )
+            .unwrap(),
+        ));
+        unsafe { Ok(WindowHandle::borrow_raw(raw_window_handle)) }
+    }
+}
+
+impl HasDisplayHandle for SessionLockWindow {
+    fn display_handle(
+        &self,
+    ) -> Result<raw_window_handle::DisplayHandle<'_>, raw_window_handle::HandleError> {
+        let raw_display_handle = RawDisplayHandle::Wayland(WaylandDisplayHandle::new(
+            core::ptr::NonNull::new(self.conn.backend().display_ptr() as *mut _).unwrap(),
+        ));
+        unsafe { Ok(DisplayHandle::borrow_raw(raw_display_handle)) }
+    }
+}
+
+pub fn create_window(
+    session_lock: &sctk_session_lock::SessionLock,
+    qh: &QueueHandle<SmithayRunnerState>,
+    surface: WlSurface,
+    output: &WlOutput,
+    conn: Connection,
+) -> SessionLockWindow {
+    let lock_surface = session_lock.create_lock_surface(surface, output, qh);
+
+    SessionLockWindow {
+        window: Some(lock_surface),
+        output: output.clone(),
+        conn,
+    }
+}
+
+impl SmithayRunnerState {
+    /// Applies lock and unlock requests made through the [`SessionLock`] resource.
+    pub(crate) fn update_session_lock(&mut self, qh: &QueueHandle<Self>) {
+        let session_lock = self.world().resource::<SessionLock>();
+        // A lock must not be destroyed before the compositor sent `locked` or `finished`.
+        if session_lock.request == Some(SessionLockRequest::Unlock)
+            && session_lock.status == SessionLockStatus::Pending
+        {
+            return;
+        }
+        let Some(request) = self
+            .world_mut()
+            .resource_mut::<SessionLock>()
+            .request
+            .take()
+        else {
+            return;
+        };
+
+        match request {
+            SessionLockRequest::Lock => {
+                if self.world().resource::<SessionLock>().status != SessionLockStatus::Unlocked {
+                    return;
+                }
+                let session_lock = match self.session_lock_state.lock(qh) {
+                    Ok(session_lock) => session_lock,
+                    Err(err) => {
+                        warn!("failed to lock the session: {}", err);
+                        return;
+                    }
+                };
+                self.world_mut()
+                    .non_send_resource_mut::<SmithayWindows>()
+                    .session_lock = Some(session_lock);
+                self.world_mut().resource_mut::<SessionLock>().status = SessionLockStatus::Pending;
+
+                for output in self.output_state.outputs().collect::<Vec<_>>() {
+                    self.spawn_lock_window(output);
+                }
+            }
+            SessionLockRequest::Unlock => {
+                if let Some(session_lock) = self
+                    .world_mut()
+                    .non_send_resource_mut::<SmithayWindows>()
+                    .session_lock
+                    .take()
+                {
+                    session_lock.unlock();
+                }
+                self.end_session_lock();
+            }
+        }
+    }
+
+    /// Spawns a lock surface window for the output if the session is being locked.
+    pub(crate) fn spawn_lock_window(&mut self, output: WlOutput) {
+        if self
+            .world()
+            .non_send_resource::<SmithayWindows>()
+            .session_lock
+            .is_none()
+        {
+            return;
+        }
+
+        let mut window = Window {
+            title: "Session Lock".to_string(),
+            ..default()
+        };
+        if let Some((width, height)) = self
+            .output_state
+            .info(&output)
+            .and_then(|info| info.logical_size)
+        {
+            window.resolution.set(width as f32, height as f32);
+        }
+        self.world_mut()
+            .spawn((window, SmithayWindowType::SessionLock { output }));
+    }
+
+    /// Despawns the lock surface window of a removed output.
+    pub(crate) fn despawn_lock_window(&mut self, output: &WlOutput) {
+        let entities = self.lock_window_entities(Some(output));
+        for entity in entities {
+            self.world_mut().despawn(entity);
+        }
+    }
+
+    fn end_session_lock(&mut self) {
+        self.world_mut().resource_mut::<SessionLock>().status = SessionLockStatus::Unlocked;
+        let entities = self.lock_window_entities(None);
+        for entity in entities {
+            self.world_mut().despawn(entity);
+        }
+    }
+
+    fn lock_window_entities(&mut self, output: Option<&WlOutput>) -> Vec<Entity> {
+        let world = self.world_mut();
+        let mut query = world.query::<(Entity, &SmithayWindowType)>();
+        query
+            .iter(world)
+            .filter_map(|(entity, window_type)| match window_type {
+                SmithayWindowType::SessionLock {
+                    output: lock_output,
+                } if output.is_none_or(|output| output == lock_output) => Some(entity),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl SessionLockHandler for SmithayRunnerState {
+    fn locked(&mut self, _: &Connection, _: &QueueHandle<Self>, _: sctk_session_lock::SessionLock) {
+        self.world_mut().resource_mut::<SessionLock>().status = SessionLockStatus::Locked;
+        self.world_mut().send_event(SessionLocked);
+    }
+
+    fn finished(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        _: sctk_session_lock::SessionLock,
+    ) {
+        self.world_mut()
+            .non_send_resource_mut::<SmithayWindows>()
+            .session_lock
+            .take();
+        // An unlock requested while the lock was pending has nothing left to do.
+        let mut session_lock = self.world_mut().resource_mut::<SessionLock>();
+        if session_lock.request == Some(SessionLockRequest::Unlock) {
+            session_lock.request = None;
+        }
+        self.end_session_lock();
+        self.world_mut().send_event(SessionLockFinished);
+    }
+
+    fn configure(
+        &mut self,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+        surface: SessionLockSurface,
+        configure: SessionLockSurfaceConfigure,
+        _: u32,
+    ) {
+        let smithay_windows = self.world().non_send_resource::<SmithayWindows>();
+        let Some(entity) = smithay_windows
+            .smithay_to_entity
+            .get(&surface.wl_surface().id())
+            .copied()
+        else {
+            return;
+        };
+
+        let (width, height) = configure.new_size;
+        if let Some(mut window) = self.world_mut().get_mut::<Window>(entity) {
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+}
+
+delegate_session_lock!(SmithayRunnerState);
@@ -23,6 +23,14 @@ pub struct SubsurfaceWindow {
     conn: Connection,
 }
 
+impl SubsurfaceWindow {
+    pub fn wl_surface(&self) -> &WlSurface {
+        self.surface
+            .as_ref()
+            .expect("trying to access subsurface after destroying")
+    }
+}
+
 impl HasWindowHandle for SubsurfaceWindow {
     fn window_handle(
         &self,
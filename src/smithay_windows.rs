@@ -2,7 +2,10 @@ use bevy::{ecs::entity::EntityHashMap, prelude::*, window::WindowWrapper};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
 use smithay_client_toolkit::{
     compositor::CompositorState,
-    reexports::client::{Connection, Proxy, QueueHandle, globals::GlobalList},
+    reexports::client::{
+        Connection, Proxy, QueueHandle, globals::GlobalList, protocol::wl_surface::WlSurface,
+    },
+    session_lock::SessionLock,
     shell::WaylandSurface,
     subcompositor::SubcompositorState,
 };
@@ -12,6 +15,7 @@ use wayland_backend::sys::client::ObjectId;
 use crate::{
     SmithayWindowType,
     prelude::layer_shell::{self, LayerShellWindow},
+    shells::session_lock::{self, SessionLockWindow},
     shells::subsurface::{self, SubsurfaceWindow},
     state::SmithayRunnerState,
 };
@@ -19,12 +23,26 @@ use crate::{
 pub enum SmithayWindow {
     LayerShellWindow(LayerShellWindow),
     SubSurface(SubsurfaceWindow),
+    SessionLock(SessionLockWindow),
 }
 
 impl SmithayWindow {
     pub fn get(&self) -> &SmithayWindow {
         self
     }
+
+    /// Returns the `wl_surface` backing the window.
+    pub fn wl_surface(&self) -> &WlSurface {
+        match self {
+            SmithayWindow::LayerShellWindow(layer_shell_window) => {
+                layer_shell_window.layer_surface().wl_surface()
+            }
+            SmithayWindow::SubSurface(subsurface_window) => subsurface_window.wl_surface(),
+            SmithayWindow::SessionLock(session_lock_window) => {
+                session_lock_window.lock_surface().wl_surface()
+            }
+        }
+    }
 }
 
 impl HasDisplayHandle for SmithayWindow {
@@ -36,6 +54,7 @@ impl HasDisplayHandle for SmithayWindow {
                 layer_shell_window.display_handle()
             }
             SmithayWindow::SubSurface(subsurface_window) => subsurface_window.display_handle(),
+            SmithayWindow::SessionLock(session_lock_window) => session_lock_window.display_handle(),
         }
     }
 }
@@ -49,6 +68,7 @@ impl HasWindowHandle for SmithayWindow {
                 layer_shell_window.window_handle()
             }
             SmithayWindow::SubSurface(subsurface_window) => subsurface_window.window_handle(),
+            SmithayWindow::SessionLock(session_lock_window) => session_lock_window.window_handle(),
         }
     }
 }
@@ -62,10 +82,42 @@ pub struct SmithayWindows {
     pub compositor: Option<CompositorState>,
     pub subcompositor: Option<SubcompositorState>,
 
+    /// The active session lock, used to create lock surfaces.
+    pub session_lock: Option<SessionLock>,
+
     _not_send_sync: core::marker::PhantomData<*const ()>,
 }
 
 impl SmithayWindows {
+    /// Returns the `wl_surface` of the window created for `entity`.
+    pub fn wl_surface(&self, entity: Entity) -> Option<&WlSurface> {
+        let window_id = self.entity_to_smithay.get(&entity)?;
+        self.windows
+            .get(window_id)
+            .map(|window| window.get().wl_surface())
+    }
+
+    /// Binds the compositor and subcompositor, unless they are already bound.
+    ///
+    /// The runner binds them on startup, as pointers need a cursor surface before any window
+    /// exists.
+    pub fn bind_compositor(&mut self, globals: &GlobalList, qh: &QueueHandle<SmithayRunnerState>) {
+        if self.compositor.is_some() {
+            return;
+        }
+        let compositor = CompositorState::bind(globals, qh).expect("faild to bind compositor");
+        let subcompositor =
+            SubcompositorState::bind(compositor.wl_compositor().clone(), globals, qh)
+                .expect("failed to bind subcompositor");
+        self.compositor = Some(compositor);
+        self.subcompositor = Some(subcompositor);
+    }
+
+    /// Returns the window entity of a surface created by this runner.
+    pub fn surface_entity(&self, surface: &WlSurface) -> Option<Entity> {
+        self.smithay_to_entity.get(&surface.id()).copied()
+    }
+
     pub fn create_window(
         &mut self,
         entity: Entity,
@@ -73,15 +125,8 @@ impl SmithayWindows {
         globals: &GlobalList,
         qh: &QueueHandle<SmithayRunnerState>,
         conn: Connection,
-    ) -> &WindowWrapper<SmithayWindow> {
-        if self.compositor.is_none() {
-            let compositor = CompositorState::bind(globals, qh).expect("faild to bind compositor");
-            let subcompositor =
-                SubcompositorState::bind(compositor.wl_compositor().clone(), globals, qh)
-                    .expect("failed to bind subcompositor");
-            self.compositor = Some(compositor);
-            self.subcompositor = Some(subcompositor);
-        }
+    ) -> Option<&WindowWrapper<SmithayWindow>> {
+        self.bind_compositor(globals, qh);
 
         match window_type {
             SmithayWindowType::LayerShell { settings } => {
@@ -101,10 +146,12 @@ impl SmithayWindows {
                 self.smithay_to_entity
                     .entry(window_id.clone())
                     .insert_entry(entity);
-                self.windows
-                    .entry(window_id.clone())
-                    .insert_entry(WindowWrapper::new(smithay_window))
-                    .into_mut()
+                Some(
+                    self.windows
+                        .entry(window_id.clone())
+                        .insert_entry(WindowWrapper::new(smithay_window))
+                        .into_mut(),
+                )
             }
             SmithayWindowType::SubSurface { parent, position } => {
                 let parent_window_id = self
@@ -121,6 +168,9 @@ impl SmithayWindows {
                     SmithayWindow::LayerShellWindow(layer_shell_window) => {
                         layer_shell_window.layer_surface().wl_surface()
                     }
+                    SmithayWindow::SessionLock(session_lock_window) => {
+                        session_lock_window.lock_surface().wl_surface()
+                    }
                     SmithayWindow::SubSurface(..) => {
                         panic!("you cannot create a subsurface of a subsurface")
                     }
@@ -152,10 +202,47 @@ impl SmithayWindows {
                 self.smithay_to_entity
                     .entry(window_id.clone())
                     .insert_entry(entity);
-                self.windows
+                Some(
+                    self.windows
+                        .entry(window_id.clone())
+                        .insert_entry(WindowWrapper::new(smithay_window))
+                        .into_mut(),
+                )
+            }
+            SmithayWindowType::SessionLock { output } => {
+                let Some(session_lock) = self.session_lock.as_ref() else {
+                    warn!(
+                        "skipping session lock window {} created without an active session lock",
+                        entity
+                    );
+                    return None;
+                };
+                let surface = self
+                    .compositor
+                    .as_mut()
+                    .expect("compositor not found")
+                    .create_surface(qh);
+                let window_id = surface.id();
+                let smithay_window = SmithayWindow::SessionLock(session_lock::create_window(
+                    session_lock,
+                    qh,
+                    surface,
+                    output,
+                    conn,
+                ));
+
+                self.entity_to_smithay
+                    .entry(entity)
+                    .insert(window_id.clone());
+                self.smithay_to_entity
                     .entry(window_id.clone())
-                    .insert_entry(WindowWrapper::new(smithay_window))
-                    .into_mut()
+                    .insert_entry(entity);
+                Some(
+                    self.windows
+                        .entry(window_id.clone())
+                        .insert_entry(WindowWrapper::new(smithay_window))
+                        .into_mut(),
+                )
             }
         }
     }
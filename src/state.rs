@@ -9,23 +9,46 @@ use bevy::{
 
 use smithay_client_toolkit::{
     compositor::CompositorHandler,
-    delegate_compositor, delegate_output, delegate_registry, delegate_seat,
+    delegate_compositor, delegate_output, delegate_registry, delegate_seat, delegate_shm,
     output::{OutputHandler, OutputState},
     reexports::{
-        calloop::EventLoop,
+        calloop::{EventLoop, LoopHandle},
         calloop_wayland_source::WaylandSource,
         client::{
-            Connection, Proxy,
+            Connection, Proxy, QueueHandle,
+            backend::ObjectId,
             globals::registry_queue_init,
-            protocol::{wl_keyboard, wl_pointer, wl_surface::WlSurface, wl_touch},
+            protocol::{wl_keyboard, wl_seat::WlSeat, wl_surface::WlSurface},
         },
     },
     registry::{ProvidesRegistryState, RegistryState},
     registry_handlers,
-    seat::{Capability, SeatHandler, SeatState},
+    seat::{
+        SeatHandler, SeatState, keyboard::KeyboardData, pointer::ThemeSpec,
+        pointer_constraints::PointerConstraintsState, relative_pointer::RelativePointerState,
+    },
+    session_lock::SessionLockState,
+    shm::{Shm, ShmHandler},
 };
 
-use crate::{CreateWindowParams, smithay_windows::SmithayWindows, system::create_windows};
+use crate::{
+    CreateWindowParams,
+    clipboard::ClipboardState,
+    idle::{IdleInhibitState, IdleNotifyState},
+    input::{
+        cursor::CustomCursorCache,
+        drag_and_drop::{ActiveDrag, FileDrag},
+        gestures::PointerGesturesState,
+        keyboard::KeyboardFocuses,
+        pointer_constraints::PointerConstraints,
+        seat::{SeatCapability, SeatDevices},
+        tablet::TabletState,
+        text_input::TextInputState,
+    },
+    input_method::InputMethodState,
+    smithay_windows::SmithayWindows,
+    system::create_windows,
+};
 
 pub fn smithay_runner(mut app: App) -> AppExit {
     if app.plugins_state() == PluginsState::Ready {
@@ -46,21 +69,45 @@ pub fn smithay_runner(mut app: App) -> AppExit {
         .expect("failed to insert wayland source to event loop");
 
     let mut smithay_runner_state = SmithayRunnerState {
+        loop_handle: loop_handle.clone(),
         registry_state: RegistryState::new(&globals),
         seat_state: SeatState::new(&globals, &qh),
         output_state: OutputState::new(&globals, &qh),
-
-        keyboard: None,
-        pointer: None,
-        touch: None,
-
-        active_keyboard_surface: None,
+        session_lock_state: SessionLockState::new(&globals, &qh),
+        idle_inhibit: IdleInhibitState::new(&globals, &qh),
+        idle_notify: IdleNotifyState::new(&globals, &qh),
+        shm: Shm::bind(&globals, &qh).expect("wl_shm not available"),
+        pointer_constraints_state: PointerConstraintsState::bind(&globals, &qh),
+        relative_pointer_state: RelativePointerState::bind(&globals, &qh),
+        pointer_gestures: PointerGesturesState::new(&globals, &qh),
+        text_input: TextInputState::new(&globals, &qh),
+        input_method: InputMethodState::new(&globals, &qh),
+        clipboard: ClipboardState::new(&globals, &qh),
+        tablet: TabletState::new(&globals, &qh),
+
+        keyboards: Vec::new(),
+        seats: Vec::new(),
+        seats_added: 0,
+
+        keyboard_focus: KeyboardFocuses::default(),
+
+        latest_input_serial: None,
+        file_drag: None,
+        drag_source: None,
+
+        custom_cursors: Default::default(),
+        pointer_constraints: Default::default(),
 
         app,
         bevy_window_events: vec![],
-
-        active_touches: Default::default(),
     };
+    smithay_runner_state
+        .world_mut()
+        .non_send_resource_mut::<SmithayWindows>()
+        .bind_compositor(&globals, &qh);
+    for seat in smithay_runner_state.seat_state.seats() {
+        smithay_runner_state.init_seat(&seat, &qh);
+    }
 
     loop {
         let mut create_window = SystemState::<CreateWindowParams<Added<Window>>>::from_world(
@@ -81,29 +128,61 @@ pub fn smithay_runner(mut app: App) -> AppExit {
             )
             .expect("an unexpected error occured");
         smithay_runner_state.run_app_update();
+        smithay_runner_state.update_seat_names();
+        smithay_runner_state.update_session_lock(&qh);
+        smithay_runner_state.update_idle_inhibitors(&qh);
+        smithay_runner_state.update_idle_notifications(&qh);
+        smithay_runner_state.update_cursor(&conn);
+        smithay_runner_state.update_pointer_constraints(&qh);
+        smithay_runner_state.update_text_inputs();
+        smithay_runner_state.update_input_method(&qh);
+        smithay_runner_state.update_clipboard(&qh);
+        smithay_runner_state.update_drag_sources(&qh);
     }
 }
 
 pub struct SmithayRunnerState {
+    pub(crate) loop_handle: LoopHandle<'static, SmithayRunnerState>,
+
     // Wayland States
     registry_state: RegistryState,
-    seat_state: SeatState,
-    output_state: OutputState,
+    pub(crate) seat_state: SeatState,
+    pub(crate) output_state: OutputState,
+    pub(crate) session_lock_state: SessionLockState,
+    pub(crate) idle_inhibit: IdleInhibitState,
+    pub(crate) idle_notify: IdleNotifyState,
+    pub(crate) shm: Shm,
+    pub(crate) pointer_constraints_state: PointerConstraintsState,
+    relative_pointer_state: RelativePointerState,
+    pub(crate) pointer_gestures: PointerGesturesState,
+    pub(crate) text_input: TextInputState,
+    pub(crate) input_method: InputMethodState,
+    pub(crate) clipboard: ClipboardState,
+    pub(crate) tablet: TabletState,
 
     // Inputs
-    pub(crate) keyboard: Option<wl_keyboard::WlKeyboard>,
-    pub(crate) pointer: Option<wl_pointer::WlPointer>,
-    pub(crate) touch: Option<wl_touch::WlTouch>,
+    pub(crate) keyboards: Vec<wl_keyboard::WlKeyboard>,
+    pub(crate) seats: Vec<SeatDevices>,
+    /// The number of seats announced so far, used to keep touch ids unique across seats.
+    pub(crate) seats_added: u32,
+
+    // Keyboard
+    pub(crate) keyboard_focus: KeyboardFocuses<ObjectId, WlSurface>,
+
+    // The seat and serial of the latest input event, required to set the selection.
+    pub(crate) latest_input_serial: Option<(WlSeat, u32)>,
 
-    // Active Surfaces
-    pub(crate) active_keyboard_surface: Option<WlSurface>,
+    // Drag and drop
+    pub(crate) file_drag: Option<FileDrag>,
+    pub(crate) drag_source: Option<ActiveDrag>,
+
+    // Cursor
+    pub(crate) custom_cursors: CustomCursorCache,
+    pub(crate) pointer_constraints: PointerConstraints,
 
     // Bevy
-    app: App,
+    pub(crate) app: App,
     pub(crate) bevy_window_events: Vec<BevyWindowEvent>,
-
-    // Touch
-    pub(crate) active_touches: std::collections::HashMap<i32, (Entity, Vec2)>,
 }
 
 impl SmithayRunnerState {
@@ -115,6 +194,31 @@ impl SmithayRunnerState {
         self.app.world_mut()
     }
 
+    /// Returns the window entity of a surface created by this runner.
+    pub(crate) fn surface_entity(&self, surface: &WlSurface) -> Option<Entity> {
+        self.world()
+            .non_send_resource::<SmithayWindows>()
+            .surface_entity(surface)
+    }
+
+    /// Spawns the entity of a seat and creates its input method, clipboard and tablet objects.
+    ///
+    /// sctk only calls `new_seat` for seats announced after startup, so this also runs for the
+    /// seats bound when the runner starts.
+    fn init_seat(&mut self, seat: &WlSeat, qh: &QueueHandle<Self>) {
+        self.add_seat(seat);
+        self.add_input_method_seat(seat, qh);
+        self.clipboard.add_seat(seat, qh);
+        self.tablet.add_seat(seat, qh);
+    }
+
+    /// Remembers the serial of an input event on `seat`, for requests that need user interaction.
+    pub(crate) fn set_latest_input_serial(&mut self, seat: Option<&WlSeat>, serial: u32) {
+        if let Some(seat) = seat {
+            self.latest_input_serial = Some((seat.clone(), serial));
+        }
+    }
+
     pub fn run_app_update(&mut self) {
         self.forward_bevy_events();
 
@@ -289,8 +393,10 @@ impl OutputHandler for SmithayRunnerState {
         &mut self,
         _: &Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput,
+        output: smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput,
     ) {
+        // Cover outputs plugged in while the session is locked.
+        self.spawn_lock_window(output);
     }
 
     fn update_output(
@@ -305,8 +411,9 @@ impl OutputHandler for SmithayRunnerState {
         &mut self,
         _: &Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput,
+        output: smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput,
     ) {
+        self.despawn_lock_window(&output);
     }
 }
 
@@ -318,9 +425,10 @@ impl SeatHandler for SmithayRunnerState {
     fn new_seat(
         &mut self,
         _: &Connection,
-        _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
+        qh: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
+        seat: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
     ) {
+        self.init_seat(&seat, qh);
     }
 
     fn new_capability(
@@ -330,19 +438,60 @@ impl SeatHandler for SmithayRunnerState {
         seat: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
         capability: smithay_client_toolkit::seat::Capability,
     ) {
-        if capability == Capability::Keyboard && self.keyboard.is_none() {
-            let keyboard = self.seat_state.get_keyboard(qh, &seat, None).unwrap();
-            self.keyboard = Some(keyboard);
+        let Ok(capability) = SeatCapability::try_from(capability) else {
+            return;
+        };
+        // Capabilities may be announced before the seat was set up.
+        self.add_seat(&seat);
+        let has_pointer = self
+            .seat_devices(&seat)
+            .is_some_and(|devices| devices.pointer.is_some());
+        if capability == SeatCapability::Keyboard {
+            let keyboard = self
+                .seat_state
+                .get_keyboard_with_repeat(
+                    qh,
+                    &seat,
+                    None,
+                    self.loop_handle.clone(),
+                    Box::new(|state, keyboard, event| state.repeat_key(keyboard, event)),
+                )
+                .unwrap();
+            self.keyboards.push(keyboard);
+            self.text_input.add_seat(&seat, qh);
+            self.set_seat_capability(&seat, capability, true);
         }
-        if capability == Capability::Pointer && self.pointer.is_none() {
-            let pointer = self.seat_state.get_pointer(qh, &seat).unwrap();
-            self.pointer = Some(pointer);
+        if capability == SeatCapability::Pointer && !has_pointer {
+            let surface = self
+                .world()
+                .non_send_resource::<SmithayWindows>()
+                .compositor
+                .as_ref()
+                .expect("compositor not found")
+                .create_surface(qh);
+            let pointer = self
+                .seat_state
+                .get_pointer_with_theme(qh, &seat, self.shm.wl_shm(), surface, ThemeSpec::System)
+                .unwrap();
+            let relative_pointer = self
+                .relative_pointer_state
+                .get_relative_pointer(pointer.pointer(), qh)
+                .ok();
+            self.pointer_gestures
+                .add_pointer(&seat, pointer.pointer(), qh);
+            let devices = self.seat_devices_mut(&seat).unwrap();
+            devices.relative_pointer = relative_pointer;
+            devices.pointer = Some(pointer);
+            self.set_seat_capability(&seat, capability, true);
         }
-        if capability == Capability::Touch && self.touch.is_none() {
+        if capability == SeatCapability::Touch
+            && self
+                .seat_devices(&seat)
+                .is_some_and(|devices| devices.touch.is_none())
+        {
             let touch = self.seat_state.get_touch(qh, &seat).unwrap();
-            self.touch = Some(touch);
-
-            info!("Touchscreen Attached");
+            self.seat_devices_mut(&seat).unwrap().touch = Some(touch);
+            self.set_seat_capability(&seat, capability, true);
         }
     }
 
@@ -350,27 +499,79 @@ impl SeatHandler for SmithayRunnerState {
         &mut self,
         _: &Connection,
         _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
+        seat: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
         capability: smithay_client_toolkit::seat::Capability,
     ) {
-        if capability == Capability::Keyboard {
-            if let Some(keyboard) = self.keyboard.take() {
+        let Ok(capability) = SeatCapability::try_from(capability) else {
+            return;
+        };
+        if capability == SeatCapability::Keyboard {
+            let (removed, keyboards) = std::mem::take(&mut self.keyboards)
+                .into_iter()
+                .partition::<Vec<_>, _>(|keyboard| {
+                    keyboard
+                        .data::<KeyboardData<Self>>()
+                        .is_some_and(|data| data.seat() == &seat)
+                });
+            self.keyboards = keyboards;
+            self.text_input.remove_seat(&seat);
+            for keyboard in removed {
+                self.end_keyboard_focus(&keyboard);
                 keyboard.release();
             }
         }
-        if capability == Capability::Pointer {
-            if let Some(pointer) = self.pointer.take() {
-                pointer.release();
+        if capability == SeatCapability::Pointer {
+            self.pointer_gestures.remove_pointer(&seat);
+            let Some(devices) = self.seat_devices_mut(&seat) else {
+                return;
+            };
+            if let Some(relative_pointer) = devices.relative_pointer.take() {
+                relative_pointer.destroy();
+            }
+            // Dropping the themed pointer releases the pointer and its cursor surface.
+            devices.pointer.take();
+            devices.pointer_focus.take();
+            devices.applied_cursor.take();
+        }
+        if capability == SeatCapability::Touch {
+            self.cancel_touches(&seat);
+            if let Some(touch) = self
+                .seat_devices_mut(&seat)
+                .and_then(|devices| devices.touch.take())
+            {
+                touch.release();
             }
         }
+        self.set_seat_capability(&seat, capability, false);
     }
 
     fn remove_seat(
         &mut self,
-        _: &Connection,
-        _: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
-        _: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
+        conn: &Connection,
+        qh: &smithay_client_toolkit::reexports::client::QueueHandle<Self>,
+        seat: smithay_client_toolkit::reexports::client::protocol::wl_seat::WlSeat,
     ) {
+        // The compositor does not announce the removal of the devices of a removed seat.
+        let capabilities = self.seat_capabilities(&seat);
+        for capability in [
+            SeatCapability::Keyboard,
+            SeatCapability::Pointer,
+            SeatCapability::Touch,
+        ] {
+            if capabilities.has(capability) {
+                self.remove_capability(conn, qh, seat.clone(), capability.into());
+            }
+        }
+        self.remove_input_method_seat(&seat);
+        self.clipboard.remove_seat(&seat);
+        self.remove_tablet_seat(&seat);
+        self.remove_seat_devices(&seat);
+    }
+}
+
+impl ShmHandler for SmithayRunnerState {
+    fn shm_state(&mut self) -> &mut Shm {
+        &mut self.shm
     }
 }
 
@@ -385,4 +586,5 @@ impl ProvidesRegistryState for SmithayRunnerState {
 delegate_compositor!(SmithayRunnerState);
 delegate_output!(SmithayRunnerState);
 delegate_seat!(SmithayRunnerState);
+delegate_shm!(SmithayRunnerState);
 delegate_registry!(SmithayRunnerState);
@@ -27,21 +27,23 @@ pub(crate) fn create_windows<F: QueryFilter + 'static>(
         mut window_created_events,
     ): SystemParamItem<CreateWindowParams<F>>,
 ) {
-    let mut smithay_window;
     for (entity, _, window_type, handle_holder) in &mut created_windows {
         if smithay_windows.entity_to_smithay.contains_key(&entity) {
             continue;
         }
-        if smithay_windows.windows.is_empty() {
-            commands.entity(entity).insert_if_new(PrimaryWindow);
-        }
-        smithay_window = smithay_windows.create_window(
+        let first_window = smithay_windows.windows.is_empty();
+        let Some(smithay_window) = smithay_windows.create_window(
             entity,
             window_type.unwrap_or(&SmithayWindowType::default()),
             globals,
             qh,
             conn.clone(),
-        );
+        ) else {
+            continue;
+        };
+        if first_window {
+            commands.entity(entity).insert_if_new(PrimaryWindow);
+        }
 
         let mut wrapper: Option<_> = None;
         if let Ok(handle_wrapper) = RawHandleWrapper::new(smithay_window) {